@@ -0,0 +1,69 @@
+use std::{
+  fs::File,
+  io::{self, Read},
+  path::Path,
+};
+
+use sha2::{Digest, Sha256};
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Size and checksums of a file, as lowercase hexadecimal strings
+#[derive(Debug, PartialEq)]
+pub struct FileDigest {
+  pub size: u64,
+  pub md5: String,
+  pub sha256: Option<String>,
+}
+
+/// Stream the file to compute its size, MD5 and optionally SHA-256 checksums
+pub fn digest_file(path: &Path, with_sha256: bool) -> io::Result<FileDigest> {
+  let mut file = File::open(path)?;
+  let mut buffer = vec![0; READ_BUFFER_SIZE];
+
+  let mut size = 0;
+  let mut md5 = md5::Context::new();
+  let mut sha256 = if with_sha256 {
+    Some(Sha256::new())
+  } else {
+    None
+  };
+
+  loop {
+    let length = file.read(&mut buffer)?;
+    if length == 0 {
+      break;
+    }
+
+    let chunk = &buffer[..length];
+    size += length as u64;
+    md5.consume(chunk);
+    if let Some(ref mut sha256) = sha256 {
+      sha256.update(chunk);
+    }
+  }
+
+  Ok(FileDigest {
+    size,
+    md5: format!("{:x}", md5.compute()),
+    sha256: sha256.map(|sha256| format!("{:x}", sha256.finalize())),
+  })
+}
+
+#[test]
+fn test_digest_file() {
+  let path = std::env::temp_dir().join(format!("rdf_worker_{}.txt", uuid::Uuid::new_v4()));
+  std::fs::write(&path, "The quick brown fox jumps over the lazy dog").unwrap();
+
+  let digest = digest_file(&path, true);
+  let _ = std::fs::remove_file(&path);
+
+  assert_eq!(
+    digest.unwrap(),
+    FileDigest {
+      size: 43,
+      md5: "9e107d9d372bb6826bd81d3542a419d6".to_string(),
+      sha256: Some("d7a8fbb307d7809469ca9abcb0082e4f8d5651e46d3cdb762d02d0bf37c9e592".to_string()),
+    }
+  );
+}
@@ -0,0 +1,404 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, prelude::*, BufReader};
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use mcai_worker_sdk::{job::JobResult, MessageError};
+use rdf::graph::Graph;
+use serde::de::DeserializeOwned;
+
+use crate::convert::{convert_into_graph, write_graph};
+use crate::format_registry::FormatRegistry;
+use crate::http::{HttpClient, RetryPolicy};
+use crate::message;
+use crate::rdf_graph::get_node_value;
+use crate::resource_model::{Resource, ResourceFilter, Resources};
+use crate::stop_signal::StopSignal;
+use crate::storage_profile::StorageProfiles;
+use crate::validation::validate_graph;
+use crate::video_model::metadata::Metadata;
+use crate::{Order, RdfWorkerParameters};
+
+/// Names of the subcommands, running the command line tool when given as first argument
+pub const SUBCOMMANDS: [&str; 4] = ["convert", "publish", "validate", "inspect"];
+
+pub fn build_app<'a, 'b>(name: &str, version: &'b str) -> App<'a, 'b> {
+  let reference = Arg::with_name("reference")
+    .help("video reference UUID")
+    .required(true)
+    .index(1);
+  let input_path = Arg::with_name("input-path")
+    .long("input-path")
+    .short("i")
+    .help("file delivered with the video, described with its metadata")
+    .takes_value(true)
+    .multiple(true)
+    .number_of_values(1);
+  let video_json = Arg::with_name("video-json")
+    .long("video-json")
+    .help("JSON file of the video metadata, or - for the standard input")
+    .takes_value(true);
+  let files_json = Arg::with_name("files-json")
+    .long("files-json")
+    .help("JSON file of the video files, or - for the standard input")
+    .takes_value(true);
+
+  App::new(name)
+    .version(version)
+    .author("Marc-Antoine Arnaud <maarnaud@media-io.com>")
+    .setting(AppSettings::SubcommandRequiredElseHelp)
+    .subcommand(
+      SubCommand::with_name("convert")
+        .about("Convert the video metadata into RDF")
+        .arg(reference.clone())
+        .arg(input_path.clone())
+        .arg(video_json.clone())
+        .arg(files_json.clone())
+        .arg(
+          Arg::with_name("format")
+            .long("format")
+            .short("f")
+            .help("RDF serialization")
+            .possible_values(&["ntriples", "turtle"])
+            .default_value("ntriples"),
+        )
+        .arg(
+          Arg::with_name("output")
+            .long("output")
+            .short("o")
+            .help("output file, or - for the standard output")
+            .default_value("-"),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("publish")
+        .about("Publish the video metadata to Perfect Memory")
+        .arg(reference.clone().required_unless("references-file"))
+        .arg(input_path.clone().conflicts_with("references-file"))
+        .arg(
+          Arg::with_name("references-file")
+            .long("references-file")
+            .help("file of video references, one per line, or - for the standard input")
+            .takes_value(true)
+            .conflicts_with("reference"),
+        )
+        .arg(
+          Arg::with_name("merge")
+            .long("merge")
+            .help("publish the references of the references file as one graph")
+            .requires("references-file"),
+        )
+        .arg(
+          Arg::with_name("parallelism")
+            .long("parallelism")
+            .help("number of references of the references file processed at once")
+            .takes_value(true)
+            .requires("references-file"),
+        )
+        .arg(
+          Arg::with_name("order")
+            .long("order")
+            .help("publication order")
+            .possible_values(&[
+              "publish_dash_and_ttml",
+              "publish_metadata",
+              "publish_transcript",
+              "retract",
+              "update",
+            ])
+            .default_value("publish_metadata"),
+        )
+        .arg(
+          Arg::with_name("endpoint")
+            .long("endpoint")
+            .env("PERFECT_MEMORY_ENDPOINT")
+            .help("Perfect Memory endpoint")
+            .required_unless("dry-run"),
+        )
+        .arg(
+          Arg::with_name("username")
+            .long("username")
+            .env("PERFECT_MEMORY_USERNAME")
+            .help("Perfect Memory client identifier")
+            .required_unless("dry-run"),
+        )
+        .arg(
+          Arg::with_name("password")
+            .long("password")
+            .env("PERFECT_MEMORY_PASSWORD")
+            .hide_env_values(true)
+            .help("Perfect Memory API key")
+            .required_unless("dry-run"),
+        )
+        .arg(
+          Arg::with_name("validate")
+            .long("validate")
+            .help("fail on constraint violations of the RDF before publishing it"),
+        )
+        .arg(
+          Arg::with_name("dry-run")
+            .long("dry-run")
+            .help("print the RDF of the Perfect Memory events instead of publishing it"),
+        )
+        .arg(
+          Arg::with_name("storage")
+            .long("storage")
+            .help("storage of the input files")
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("url-prefix")
+            .long("url-prefix")
+            .help("URL prefix of the input files")
+            .takes_value(true),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("validate")
+        .about("Check the RDF of the video metadata against the shapes of its classes")
+        .arg(reference.clone())
+        .arg(input_path.clone())
+        .arg(video_json.clone())
+        .arg(files_json.clone()),
+    )
+    .subcommand(
+      SubCommand::with_name("inspect")
+        .about("Print the number of triples per predicate of the video metadata")
+        .arg(reference)
+        .arg(input_path)
+        .arg(video_json)
+        .arg(files_json),
+    )
+}
+
+/// Run a subcommand, and return its error message on failure
+pub fn run(matches: &ArgMatches, stop_signal: &StopSignal) -> Result<(), String> {
+  match matches.subcommand() {
+    ("convert", Some(matches)) => convert(matches),
+    ("publish", Some(matches)) => publish(matches, stop_signal),
+    ("validate", Some(matches)) => validate(matches),
+    ("inspect", Some(matches)) => inspect(matches),
+    _ => Err(matches.usage().to_string()),
+  }
+}
+
+fn convert(matches: &ArgMatches) -> Result<(), String> {
+  let graph = get_graph(matches)?;
+  let n_triples = matches.value_of("format") == Some("ntriples");
+  let rdf = write_graph(&graph, n_triples).map_err(|error| error.to_string())?;
+
+  match matches.value_of("output") {
+    Some("-") | None => io::stdout().write_all(rdf.as_bytes()),
+    Some(output) => File::create(output).and_then(|mut file| file.write_all(rdf.as_bytes())),
+  }
+  .map_err(|error| format!("Unable to write RDF: {}", error))
+}
+
+fn publish(matches: &ArgMatches, stop_signal: &StopSignal) -> Result<(), String> {
+  let order = matches.value_of("order").unwrap_or_default();
+  let order: Order = serde_json::from_value(serde_json::Value::String(order.to_string()))
+    .map_err(|error| error.to_string())?;
+
+  let references = match matches.value_of("references-file") {
+    Some(path) => Some(read_references(path)?),
+    None => None,
+  };
+  let batch_parallelism = match matches.value_of("parallelism") {
+    Some(parallelism) => Some(
+      parallelism
+        .parse()
+        .map_err(|_| format!("Invalid parallelism: {}", parallelism))?,
+    ),
+    None => None,
+  };
+
+  let parameters = RdfWorkerParameters {
+    batch_parallelism,
+    dry_run: Some(matches.is_present("dry-run")),
+    input_paths: Some(get_input_paths(matches)),
+    merge_references: Some(matches.is_present("merge")),
+    order: Some(order),
+    perfect_memory_endpoint: matches.value_of("endpoint").map(String::from),
+    perfect_memory_username: matches.value_of("username").map(String::from),
+    perfect_memory_password: matches.value_of("password").map(String::from),
+    reference: get_reference(matches).to_string(),
+    references,
+    storage: matches.value_of("storage").map(String::from),
+    url_prefix: matches.value_of("url-prefix").map(String::from),
+    validate_graph: Some(matches.is_present("validate")),
+    ..Default::default()
+  };
+
+  let job_result = message::process(None, parameters, JobResult::new(0), stop_signal);
+  let job_result = match job_result {
+    Err(MessageError::ProcessingError(job_result)) => {
+      print_references(&job_result);
+      return Err(get_error_message(MessageError::ProcessingError(job_result)));
+    }
+    job_result => job_result.map_err(get_error_message)?,
+  };
+  print_references(&job_result);
+  print_publications(&job_result);
+  if let Some(message) = message::get_message(&job_result) {
+    println!("{}", message);
+  }
+  Ok(())
+}
+
+/// Print the report of each reference of a batch
+fn print_references(job_result: &JobResult) {
+  for report in get_json_list(job_result, "references") {
+    println!(
+      "{} {} {}",
+      report["reference"].as_str().unwrap_or_default(),
+      report["status"].as_str().unwrap_or_default(),
+      report["message"].as_str().unwrap_or_default()
+    );
+  }
+}
+
+/// Print the RDF of the events of a dry run
+fn print_publications(job_result: &JobResult) {
+  for publication in get_json_list(job_result, "publications") {
+    println!(
+      "{}",
+      publication["rdf"].as_str().unwrap_or_default().trim_end()
+    );
+  }
+}
+
+/// List set as a JSON string by `JobResult::with_json`
+fn get_json_list(job_result: &JobResult, id: &str) -> Vec<serde_json::Value> {
+  job_result
+    .get_parameters()
+    .iter()
+    .find(|parameter| parameter.id == id)
+    .and_then(|parameter| parameter.value.as_ref())
+    .and_then(|value| value.as_str())
+    .and_then(|json| serde_json::from_str(json).ok())
+    .unwrap_or_default()
+}
+
+fn validate(matches: &ArgMatches) -> Result<(), String> {
+  let graph = get_graph(matches)?;
+  let violations = validate_graph(&graph);
+  for violation in &violations {
+    println!("{}: {}", violation.focus_node, violation.message);
+  }
+
+  if violations.is_empty() {
+    println!("{} triples conform", graph.count());
+    Ok(())
+  } else {
+    Err(format!("{} constraint violations", violations.len()))
+  }
+}
+
+fn inspect(matches: &ArgMatches) -> Result<(), String> {
+  let graph = get_graph(matches)?;
+
+  let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+  for triple in graph.triples_iter() {
+    *counts
+      .entry(get_node_value(triple.predicate()))
+      .or_insert(0) += 1;
+  }
+
+  for (predicate, count) in counts {
+    println!("{:>6} {}", count, predicate);
+  }
+  println!("{:>6} triples", graph.count());
+  Ok(())
+}
+
+fn get_reference<'a>(matches: &'a ArgMatches) -> &'a str {
+  matches.value_of("reference").unwrap_or_default()
+}
+
+fn get_input_paths(matches: &ArgMatches) -> Vec<String> {
+  matches
+    .values_of("input-path")
+    .map(|paths| paths.map(String::from).collect())
+    .unwrap_or_default()
+}
+
+/// Graph of the video metadata, built like the `publish_metadata` order does.
+///
+/// Video metadata and files are read from the JSON files given with `--video-json` and
+/// `--files-json` (`-` being the standard input), or requested to the video factory otherwise.
+fn get_graph(matches: &ArgMatches) -> Result<Graph, String> {
+  let reference = get_reference(matches);
+  let video_json = matches.value_of("video-json");
+  let files_json = matches.value_of("files-json");
+  if video_json == Some("-") && files_json == Some("-") {
+    return Err(
+      "Only one of --video-json and --files-json can be read from the standard input".to_string(),
+    );
+  }
+
+  let http_client = HttpClient::new(RetryPolicy::default())?;
+  let format_registry = FormatRegistry::from_env()?;
+  let storage_profile = StorageProfiles::from_env()?.resolve(message::LTS_STORAGE, None);
+
+  let mut video_metadata: Metadata = match video_json {
+    Some(path) => read_json(path)?,
+    None => message::get_video_metadata(&http_client, reference)?,
+  };
+  let mut files: Vec<Resource> = match files_json {
+    Some(path) => read_json(path)?,
+    None => message::get_files(&http_client, reference, &ResourceFilter::default())?,
+  };
+
+  files.extend(message::get_input_resources(
+    &format_registry,
+    &get_input_paths(matches),
+    reference,
+    &storage_profile,
+    &["lts".to_string()],
+    false,
+  )?);
+  video_metadata.resources = Resources { items: files };
+
+  convert_into_graph(&video_metadata).map_err(|error| error.to_string())
+}
+
+fn read_references(path: &str) -> Result<Vec<String>, String> {
+  let content = if path == "-" {
+    let mut content = String::new();
+    io::stdin().read_to_string(&mut content).map(|_| content)
+  } else {
+    std::fs::read_to_string(path)
+  }
+  .map_err(|error| format!("Unable to read {}: {}", path, error))?;
+
+  Ok(
+    content
+      .lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty())
+      .map(String::from)
+      .collect(),
+  )
+}
+
+fn read_json<T: DeserializeOwned>(path: &str) -> Result<T, String> {
+  let result = if path == "-" {
+    serde_json::from_reader(io::stdin())
+  } else {
+    let file = File::open(path).map_err(|error| format!("Unable to read {}: {}", path, error))?;
+    serde_json::from_reader(BufReader::new(file))
+  };
+  result.map_err(|error| format!("Invalid JSON in {}: {}", path, error))
+}
+
+fn get_error_message(error: MessageError) -> String {
+  match error {
+    MessageError::ProcessingError(job_result) => {
+      message::get_message(&job_result).unwrap_or_else(|| "Processing error".to_string())
+    }
+    MessageError::RuntimeError(message)
+    | MessageError::ParameterValueError(message)
+    | MessageError::RequirementsError(message) => message,
+    MessageError::NotImplemented() => "Not implemented".to_string(),
+  }
+}
@@ -20,6 +20,7 @@ use rdf::{
   error::Error,
   graph::Graph,
   namespace::Namespace,
+  reader::{n_triples_parser::NTriplesParser, rdf_parser::RdfParser},
   uri::Uri,
   writer::{n_triples_writer::NTriplesWriter, rdf_writer::RdfWriter, turtle_writer::TurtleWriter},
 };
@@ -28,7 +29,20 @@ pub trait ToRdf {
   fn to_rdf(&self, graph: &mut Graph) -> Result<(), Error>;
 }
 
+#[cfg(test)]
 pub fn convert_into_rdf<T: ToRdf>(item: &T, n_triples: bool) -> Result<String, Error> {
+  let graph = convert_into_graph(item)?;
+  write_graph(&graph, n_triples)
+}
+
+pub fn convert_into_graph<T: ToRdf>(item: &T) -> Result<Graph, Error> {
+  let mut graph = new_graph();
+  item.to_rdf(&mut graph)?;
+  Ok(graph)
+}
+
+/// Empty graph with the namespaces used by the conversion
+pub fn new_graph() -> Graph {
   let mut graph = Graph::new(None);
   graph.add_namespace(&Namespace::new(
     "rdf".to_string(),
@@ -58,19 +72,25 @@ pub fn convert_into_rdf<T: ToRdf>(item: &T, n_triples: bool) -> Result<String, E
     "default".to_string(),
     Uri::new(DEFAULT_NAMESPACE.to_owned()),
   ));
+  graph
+}
 
-  item.to_rdf(&mut graph)?;
+pub fn write_graph(graph: &Graph, n_triples: bool) -> Result<String, Error> {
   if n_triples {
     let writer = NTriplesWriter::new();
-    writer.write_to_string(&graph)
+    writer.write_to_string(graph)
   } else {
     let writer = TurtleWriter::new(graph.namespaces());
-    writer.write_to_string(&graph)
+    writer.write_to_string(graph)
   }
 }
 
+pub fn read_n_triples(content: &str) -> Result<Graph, Error> {
+  NTriplesParser::from_string(content).decode()
+}
+
 #[cfg(test)]
-use crate::resource_model::{ExternalIds, Format, Resource, Resources};
+use crate::resource_model::{Format, Resource, Resources};
 
 #[test]
 fn test_mapping_video() {
@@ -108,46 +128,18 @@ fn test_mapping_resource() {
   use std::fs::File;
   use std::io::Read;
 
-  let resource = Resource {
-    id: "000000-1111-2222-3333-44444444".to_string(),
-    created_via: "Media-IO".to_string(),
-    format: Format {
-      id: "playlist-hls".to_string(),
-      label: "playlist/hls".to_string(),
-      kind: "playlist".to_string(),
-      mime_type: "urn:mimetype:application/dash+xml".to_string(),
-    },
-    storage: "akamai-video-prod".to_string(),
-    path: None,
-    filename: Some("/path/to/manifest.mpd".to_string()),
-    audio_tracks: vec![],
-    text_tracks: vec![],
-    video_tracks: vec![],
-    created_at: None,
-    updated_at: None,
-    ratio: None,
-    width: None,
-    height: None,
-    index: None,
-    copyright: None,
-    filesize_bytes: None,
-    bitrate_kbps: None,
-    md5_checksum: None,
-    tags: vec![],
-    url: Some(format!(
-      "{}{}",
-      "http://videos-pmd.francetv.fr/innovation/SubTil/", "/path/to/manifest.mpd"
-    )),
-    version: None,
-    lang: None,
-    external_ids: ExternalIds {
-      video_id: Some("44444444-3333-2222-1111-000000".to_string()),
-      legacy_id: None,
-      group_id: None,
-      job_id: None,
-      remote_id: None,
-    },
+  let format = Format {
+    id: "playlist-hls".to_string(),
+    label: "playlist/hls".to_string(),
+    kind: "playlist".to_string(),
+    mime_type: "urn:mimetype:application/dash+xml".to_string(),
   };
+  let mut resource = Resource::builder("/path/to/manifest.mpd", format)
+    .storage("akamai-video-prod")
+    .url_prefix("http://videos-pmd.francetv.fr/innovation/SubTil/")
+    .reference("44444444-3333-2222-1111-000000")
+    .build();
+  resource.id = "000000-1111-2222-3333-44444444".to_string();
 
   let rdf_triples = convert_into_rdf(&resource, true).unwrap();
 
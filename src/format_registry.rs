@@ -0,0 +1,233 @@
+use std::{env, fs};
+
+use crate::resource_model::Format;
+
+/// Environment variable pointing to a JSON file with additional registry entries
+pub const FORMAT_REGISTRY_PATH_ENV: &str = "FORMAT_REGISTRY_PATH";
+
+/// Format and tags given to the files whose name ends with `suffix`
+#[derive(Clone, Debug, Deserialize)]
+pub struct FormatEntry {
+  pub suffix: String,
+  pub format: Format,
+  #[serde(default)]
+  pub tags: Vec<String>,
+}
+
+/// Maps file extensions and naming patterns to resource formats.
+///
+/// The longest matching suffix wins, so naming patterns like `-qad.mp4` take precedence over
+/// the plain `.mp4` extension. Matching is case insensitive.
+#[derive(Clone, Debug)]
+pub struct FormatRegistry {
+  entries: Vec<FormatEntry>,
+}
+
+impl Default for FormatRegistry {
+  fn default() -> Self {
+    let entries = vec![
+      entry(
+        ".ttml",
+        "caption-ttml",
+        "caption/ttml",
+        "caption",
+        "application/xml+ttml",
+        &[],
+      ),
+      entry(
+        ".vtt",
+        "caption-vtt",
+        "caption/vtt",
+        "caption",
+        "text/vtt",
+        &[],
+      ),
+      entry(
+        ".srt",
+        "caption-srt",
+        "caption/srt",
+        "caption",
+        "application/x-subrip",
+        &[],
+      ),
+      entry(
+        ".mpd",
+        "playlist-dash",
+        "playlist/dash",
+        "playlist",
+        "application/dash+xml",
+        &[],
+      ),
+      entry(
+        ".m3u8",
+        "playlist-hls",
+        "playlist/hls",
+        "playlist",
+        "application/x-mpegURL",
+        &[],
+      ),
+      entry(
+        ".ism",
+        "playlist-smooth",
+        "playlist/smooth",
+        "playlist",
+        "application/vnd.ms-sstr+xml",
+        &[],
+      ),
+      entry(".mp4", "video-mp4", "video/mp4", "video", "video/mp4", &[]),
+      entry(
+        "-qaa.mp4",
+        "video-mp4",
+        "video/mp4",
+        "video",
+        "video/mp4",
+        &["qaa"],
+      ),
+      entry(
+        "-qad.mp4",
+        "video-mp4",
+        "video/mp4",
+        "video",
+        "video/mp4",
+        &["qad"],
+      ),
+      entry(
+        ".mxf",
+        "video-mxf",
+        "video/mxf",
+        "video",
+        "application/mxf",
+        &[],
+      ),
+      entry(".mp3", "audio-mp3", "audio/mp3", "audio", "audio/mpeg", &[]),
+      entry(".aac", "audio-aac", "audio/aac", "audio", "audio/aac", &[]),
+      entry(
+        ".jpg",
+        "image-jpeg",
+        "image/jpeg",
+        "image",
+        "image/jpeg",
+        &[],
+      ),
+      entry(
+        ".jpeg",
+        "image-jpeg",
+        "image/jpeg",
+        "image",
+        "image/jpeg",
+        &[],
+      ),
+      entry(".png", "image-png", "image/png", "image", "image/png", &[]),
+    ];
+
+    FormatRegistry { entries }
+  }
+}
+
+fn entry(
+  suffix: &str,
+  id: &str,
+  label: &str,
+  kind: &str,
+  mime_type: &str,
+  tags: &[&str],
+) -> FormatEntry {
+  FormatEntry {
+    suffix: suffix.to_string(),
+    format: Format {
+      id: id.to_string(),
+      label: label.to_string(),
+      kind: kind.to_string(),
+      mime_type: mime_type.to_string(),
+    },
+    tags: tags.iter().map(|tag| tag.to_string()).collect(),
+  }
+}
+
+impl FormatRegistry {
+  /// Built-in registry, extended with the file set in `FORMAT_REGISTRY_PATH` if any
+  pub fn from_env() -> Result<Self, String> {
+    let mut registry = FormatRegistry::default();
+    if let Ok(path) = env::var(FORMAT_REGISTRY_PATH_ENV) {
+      registry.load(&path)?;
+    }
+    Ok(registry)
+  }
+
+  /// Add the entries of a JSON file, they override built-in entries with the same suffix
+  pub fn load(&mut self, path: &str) -> Result<(), String> {
+    let content = fs::read_to_string(path)
+      .map_err(|error| format!("Unable to read format registry {}: {}", path, error))?;
+    let entries: Vec<FormatEntry> = serde_json::from_str(&content)
+      .map_err(|error| format!("Invalid format registry {}: {}", path, error))?;
+
+    self.extend(entries);
+    Ok(())
+  }
+
+  pub fn extend(&mut self, entries: Vec<FormatEntry>) {
+    for entry in entries {
+      self
+        .entries
+        .retain(|existing| !existing.suffix.eq_ignore_ascii_case(&entry.suffix));
+      self.entries.push(entry);
+    }
+  }
+
+  pub fn find(&self, path: &str) -> Option<&FormatEntry> {
+    let path = path.to_lowercase();
+    self
+      .entries
+      .iter()
+      .filter(|entry| path.ends_with(&entry.suffix.to_lowercase()))
+      .max_by_key(|entry| entry.suffix.len())
+  }
+}
+
+#[test]
+fn test_find_format() {
+  let registry = FormatRegistry::default();
+
+  let entry = registry.find("subtitles/FRA.TTML").unwrap();
+  assert_eq!(entry.format.id, "caption-ttml");
+  assert!(entry.tags.is_empty());
+
+  let entry = registry.find("video-qad.mp4").unwrap();
+  assert_eq!(entry.format.id, "video-mp4");
+  assert_eq!(entry.tags, vec!["qad".to_string()]);
+
+  let entry = registry.find("master.m3u8").unwrap();
+  assert_eq!(entry.format.mime_type, "application/x-mpegURL");
+
+  assert!(registry.find("archive.zip").is_none());
+}
+
+#[test]
+fn test_extend_format_registry() {
+  let mut registry = FormatRegistry::default();
+  let entries: Vec<FormatEntry> = serde_json::from_str(
+    r#"[
+      {
+        "suffix": ".mov",
+        "format": {"id": "video-mov", "label": "video/mov", "type": "video", "mime_type": "video/quicktime"}
+      },
+      {
+        "suffix": ".mp4",
+        "format": {"id": "video-mp4", "label": "video/mp4", "type": "video", "mime_type": "video/mp4"},
+        "tags": ["lts"]
+      }
+    ]"#,
+  )
+  .unwrap();
+  registry.extend(entries);
+
+  assert_eq!(registry.find("clip.mov").unwrap().format.id, "video-mov");
+  assert_eq!(
+    registry.find("clip.mp4").unwrap().tags,
+    vec!["lts".to_string()]
+  );
+  assert_eq!(
+    registry.find("clip-qaa.mp4").unwrap().tags,
+    vec!["qaa".to_string()]
+  );
+}
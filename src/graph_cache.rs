@@ -0,0 +1,51 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rdf::graph::Graph;
+
+use crate::convert::{read_n_triples, write_graph};
+
+/// Local copy of the last graph published for each video, stored as N-Triples
+pub struct GraphCache {
+  directory: PathBuf,
+}
+
+impl GraphCache {
+  pub fn new(directory: &str) -> Self {
+    GraphCache {
+      directory: Path::new(directory).to_path_buf(),
+    }
+  }
+
+  fn get_path(&self, reference: &str) -> PathBuf {
+    self.directory.join(format!("{}.nt", reference))
+  }
+
+  pub fn load(&self, reference: &str) -> Result<Option<Graph>, String> {
+    let path = self.get_path(reference);
+    if !path.exists() {
+      return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path)
+      .map_err(|error| format!("Unable to read {}: {}", path.display(), error))?;
+    read_n_triples(&content)
+      .map(Some)
+      .map_err(|error| format!("Invalid cached graph {}: {}", path.display(), error))
+  }
+
+  pub fn store(&self, reference: &str, graph: &Graph) -> Result<(), String> {
+    fs::create_dir_all(&self.directory).map_err(|error| {
+      format!(
+        "Unable to create cache directory {}: {}",
+        self.directory.display(),
+        error
+      )
+    })?;
+
+    let content = write_graph(graph, true).map_err(|error| error.to_string())?;
+    let path = self.get_path(reference);
+    fs::write(&path, content)
+      .map_err(|error| format!("Unable to write {}: {}", path.display(), error))
+  }
+}
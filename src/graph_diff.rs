@@ -0,0 +1,185 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use rdf::{graph::Graph, namespace::Namespace, node::Node, triple::Triple};
+
+/// Triples to delete from a previous graph and to insert to get the current one
+pub struct GraphDiff {
+  pub deleted: Graph,
+  pub inserted: Graph,
+}
+
+/// Part of a graph made of a triple and all the blank nodes reachable from its object
+struct Branch<'a> {
+  signature: String,
+  triples: Vec<&'a Triple>,
+}
+
+/// Compare two graphs branch by branch.
+///
+/// Blank node labels are not stable between two conversions (or two stores), so a branch is
+/// identified by its content only. A modified branch is deleted and inserted again as a whole.
+pub fn diff_graphs(previous: &Graph, current: &Graph) -> GraphDiff {
+  let previous_branches = get_branches(previous);
+  let current_branches = get_branches(current);
+
+  let mut deleted = new_graph_like(current);
+  let mut inserted = new_graph_like(current);
+
+  let mut previous_signatures = count_signatures(&previous_branches);
+  let mut current_signatures = count_signatures(&current_branches);
+
+  for branch in &current_branches {
+    if !take_signature(&mut previous_signatures, &branch.signature) {
+      for triple in &branch.triples {
+        inserted.add_triple(triple);
+      }
+    }
+  }
+
+  for branch in &previous_branches {
+    if !take_signature(&mut current_signatures, &branch.signature) {
+      for triple in &branch.triples {
+        deleted.add_triple(triple);
+      }
+    }
+  }
+
+  GraphDiff { deleted, inserted }
+}
+
+fn new_graph_like(graph: &Graph) -> Graph {
+  let mut new_graph = Graph::new(graph.base_uri().as_ref());
+  for (prefix, uri) in graph.namespaces() {
+    new_graph.add_namespace(&Namespace::new(prefix.clone(), uri.clone()));
+  }
+  new_graph
+}
+
+fn count_signatures<'a>(branches: &'a [Branch<'_>]) -> BTreeMap<&'a str, usize> {
+  let mut signatures = BTreeMap::new();
+  for branch in branches {
+    *signatures.entry(branch.signature.as_str()).or_insert(0) += 1;
+  }
+  signatures
+}
+
+fn take_signature(signatures: &mut BTreeMap<&str, usize>, signature: &str) -> bool {
+  match signatures.get_mut(signature) {
+    Some(count) if *count > 0 => {
+      *count -= 1;
+      true
+    }
+    _ => false,
+  }
+}
+
+fn get_branches(graph: &Graph) -> Vec<Branch<'_>> {
+  let blank_objects: BTreeSet<&Node> = graph
+    .triples_iter()
+    .map(|triple| triple.object())
+    .filter(|node| is_blank(node))
+    .collect();
+
+  graph
+    .triples_iter()
+    .filter(|triple| !blank_objects.contains(triple.subject()))
+    .map(|triple| {
+      let mut triples = vec![triple];
+      let mut visited = BTreeSet::new();
+      let signature = format!(
+        "{} {}",
+        get_node_signature(graph, triple.subject(), &mut vec![], &mut visited),
+        get_edge_signature(graph, triple, &mut triples, &mut visited)
+      );
+      Branch { signature, triples }
+    })
+    .collect()
+}
+
+fn get_edge_signature<'a>(
+  graph: &'a Graph,
+  triple: &'a Triple,
+  triples: &mut Vec<&'a Triple>,
+  visited: &mut BTreeSet<&'a Node>,
+) -> String {
+  format!(
+    "{:?} {}",
+    triple.predicate(),
+    get_node_signature(graph, triple.object(), triples, visited)
+  )
+}
+
+fn get_node_signature<'a>(
+  graph: &'a Graph,
+  node: &'a Node,
+  triples: &mut Vec<&'a Triple>,
+  visited: &mut BTreeSet<&'a Node>,
+) -> String {
+  if !is_blank(node) {
+    return format!("{:?}", node);
+  }
+
+  if !visited.insert(node) {
+    return "[...]".to_string();
+  }
+
+  let mut edges = vec![];
+  for triple in graph.get_triples_with_subject(node) {
+    triples.push(triple);
+    edges.push(get_edge_signature(graph, triple, triples, visited));
+  }
+  edges.sort();
+
+  format!("[{}]", edges.join(" ; "))
+}
+
+fn is_blank(node: &Node) -> bool {
+  matches!(node, Node::BlankNode { .. })
+}
+
+#[test]
+fn test_diff_graphs() {
+  use crate::convert::{read_n_triples, write_graph};
+
+  let previous = read_n_triples(
+    r#"<http://example.org/video> <http://example.org/title> "Title" .
+<http://example.org/video> <http://example.org/hasResource> _:b0 .
+_:b0 <http://example.org/filename> "video.mp4" .
+<http://example.org/video> <http://example.org/hasResource> _:b1 .
+_:b1 <http://example.org/filename> "subtitles.ttml" .
+"#,
+  )
+  .unwrap();
+
+  let current = read_n_triples(
+    r#"<http://example.org/video> <http://example.org/title> "Title" .
+<http://example.org/video> <http://example.org/hasResource> _:auto0 .
+_:auto0 <http://example.org/filename> "subtitles.ttml" .
+<http://example.org/video> <http://example.org/hasResource> _:auto1 .
+_:auto1 <http://example.org/filename> "video.mp4" .
+"#,
+  )
+  .unwrap();
+
+  let diff = diff_graphs(&previous, &current);
+  assert!(diff.deleted.is_empty());
+  assert!(diff.inserted.is_empty());
+
+  let current = read_n_triples(
+    r#"<http://example.org/video> <http://example.org/title> "New title" .
+<http://example.org/video> <http://example.org/hasResource> _:auto0 .
+_:auto0 <http://example.org/filename> "subtitles.ttml" .
+<http://example.org/video> <http://example.org/hasResource> _:auto1 .
+_:auto1 <http://example.org/filename> "video-hd.mp4" .
+"#,
+  )
+  .unwrap();
+
+  let diff = diff_graphs(&previous, &current);
+  assert_eq!(diff.deleted.count(), 3);
+  assert_eq!(diff.inserted.count(), 3);
+  let inserted = write_graph(&diff.inserted, true).unwrap();
+  assert!(inserted.contains(r#""New title""#));
+  assert!(inserted.contains(r#""video-hd.mp4""#));
+  assert!(!inserted.contains(r#""subtitles.ttml""#));
+}
@@ -0,0 +1,169 @@
+use mcai_worker_sdk::{
+  info,
+  job::{JobResult, JobStatus},
+  JsonSchema, McaiChannel, MessageError, Result,
+};
+use reqwest::{header::CONTENT_TYPE, StatusCode};
+
+use crate::http::{HttpClient, Idempotency};
+use crate::publisher::{Event, EventKind, Publisher};
+use crate::sparql::get_video_uri;
+use crate::stop_signal::StopSignal;
+
+/// HTTP method publishing the whole graph of a video to its named graph
+#[derive(Clone, Copy, Debug, Default, Deserialize, JsonSchema, PartialEq)]
+pub enum GraphStoreMethod {
+  /// Replace the named graph
+  #[default]
+  #[serde(rename = "put")]
+  Put,
+  /// Merge the triples into the named graph
+  #[serde(rename = "post")]
+  Post,
+}
+
+#[derive(Debug)]
+pub(crate) struct GraphStoreConfig {
+  pub(crate) endpoint: String,
+  pub(crate) username: Option<String>,
+  pub(crate) password: Option<String>,
+  pub(crate) method: GraphStoreMethod,
+}
+
+/// Publisher storing the graph of each reference in a named graph of a SPARQL 1.1 Graph Store
+/// Protocol endpoint, like the `/data` service of Fuseki
+pub(crate) struct GraphStorePublisher {
+  pub(crate) http_client: HttpClient,
+  pub(crate) config: GraphStoreConfig,
+}
+
+impl Publisher for GraphStorePublisher {
+  fn publishes_diff(&self) -> bool {
+    false
+  }
+
+  fn publish(
+    &self,
+    job_result: JobResult,
+    _channel: &Option<McaiChannel>,
+    event: &Event,
+    _stop_signal: &StopSignal,
+  ) -> Result<JobResult> {
+    let processing_error = |message: &str| {
+      MessageError::ProcessingError(
+        job_result
+          .clone()
+          .with_status(JobStatus::Error)
+          .with_message(message),
+      )
+    };
+
+    let reference = event.reference.ok_or_else(|| {
+      processing_error("The Graph Store Protocol publisher needs a named graph per reference")
+    })?;
+    let graph_uri = get_video_uri(reference);
+
+    // Inserted triples are always merged, the other triples of the named graph being kept
+    let (request, idempotency) = match (event.kind, self.config.method) {
+      (EventKind::Insert, _) | (EventKind::Replace, GraphStoreMethod::Post) => (
+        self.http_client.post(&self.config.endpoint),
+        Idempotency::NonIdempotent,
+      ),
+      (EventKind::Replace, GraphStoreMethod::Put) => (
+        self.http_client.put(&self.config.endpoint),
+        Idempotency::Idempotent,
+      ),
+      (EventKind::Retract, _) => (
+        self.http_client.delete(&self.config.endpoint),
+        Idempotency::Idempotent,
+      ),
+      (EventKind::Delete, _) => {
+        return Err(processing_error(
+          "The Graph Store Protocol publisher can not delete some triples of a graph",
+        ))
+      }
+    };
+
+    let mut request = request.query(&[("graph", &graph_uri)]);
+    if event.kind != EventKind::Retract {
+      let content_type = if event.n_triples {
+        "application/n-triples"
+      } else {
+        "text/turtle"
+      };
+      request = request
+        .header(CONTENT_TYPE, content_type)
+        .body(event.rdf.to_string());
+    }
+    if let Some(username) = &self.config.username {
+      request = request.basic_auth(username, self.config.password.as_ref());
+    }
+
+    info!("Publish graph {} to {}", graph_uri, self.config.endpoint);
+    let response = self
+      .http_client
+      .send(request, idempotency)
+      .map_err(|error| processing_error(&error))?;
+
+    let status = response.status();
+    // Retracting a video never published is not an error
+    let retracted = event.kind == EventKind::Retract && status == StatusCode::NOT_FOUND;
+    if !status.is_success() && !retracted {
+      let text = response.text().unwrap_or_default();
+      return Err(processing_error(&format!(
+        "Unable to publish graph {} to the Graph Store: {} {}",
+        graph_uri, status, text
+      )));
+    }
+
+    Ok(job_result)
+  }
+}
+
+#[test]
+fn test_graph_store_publisher() {
+  use crate::http::{start_test_server, test_event, test_graph, RetryPolicy, TEST_RDF};
+
+  let (address, server) = start_test_server(3, 201);
+  let publisher = GraphStorePublisher {
+    http_client: HttpClient::new(RetryPolicy::default()).unwrap(),
+    config: GraphStoreConfig {
+      endpoint: format!("{}/ds/data", address),
+      username: Some("admin".to_string()),
+      password: Some("secret".to_string()),
+      method: GraphStoreMethod::Put,
+    },
+  };
+
+  let graph = test_graph();
+  let mut event = test_event(&graph);
+  let stop_signal = StopSignal::default();
+  publisher
+    .publish(JobResult::new(0), &None, &event, &stop_signal)
+    .unwrap();
+
+  event.kind = EventKind::Replace;
+  publisher
+    .publish(JobResult::new(0), &None, &event, &stop_signal)
+    .unwrap();
+
+  event.kind = EventKind::Retract;
+  publisher
+    .publish(JobResult::new(0), &None, &event, &stop_signal)
+    .unwrap();
+
+  event.kind = EventKind::Delete;
+  assert!(publisher
+    .publish(JobResult::new(0), &None, &event, &stop_signal)
+    .is_err());
+
+  let requests = server.join().unwrap();
+  assert!(requests[0].starts_with(
+    "POST /ds/data?graph=http%3A%2F%2Fresources.idfrancetv.fr%2Fmedias%2F99787afd HTTP/1.1\r\n"
+  ));
+  assert!(requests[0].contains("content-type: application/n-triples\r\n"));
+  assert!(requests[0].contains("authorization: Basic YWRtaW46c2VjcmV0\r\n"));
+  assert!(requests[0].ends_with(TEST_RDF));
+  assert!(requests[1].starts_with("PUT /ds/data?graph="));
+  assert!(requests[2].starts_with("DELETE /ds/data?graph="));
+}
@@ -0,0 +1,394 @@
+use std::{
+  thread,
+  time::{Duration, Instant, SystemTime},
+};
+
+use mcai_worker_sdk::{error, info, JsonSchema};
+use rand::Rng;
+use reqwest::{
+  blocking::{Client, RequestBuilder, Response},
+  header::RETRY_AFTER,
+  IntoUrl, StatusCode,
+};
+
+use crate::stop_signal::StopSignal;
+
+/// Retry policy applied to HTTP calls, with an exponential backoff between attempts
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct RetryPolicy {
+  #[serde(default = "default_max_attempts")]
+  pub max_attempts: u32,
+  #[serde(default = "default_initial_delay_ms")]
+  pub initial_delay_ms: u64,
+  #[serde(default = "default_max_delay_ms")]
+  pub max_delay_ms: u64,
+  #[serde(default = "default_multiplier")]
+  pub multiplier: f64,
+  #[serde(default = "default_jitter")]
+  pub jitter: bool,
+}
+
+fn default_max_attempts() -> u32 {
+  5
+}
+
+fn default_initial_delay_ms() -> u64 {
+  500
+}
+
+fn default_max_delay_ms() -> u64 {
+  30_000
+}
+
+fn default_multiplier() -> f64 {
+  2.0
+}
+
+fn default_jitter() -> bool {
+  true
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    RetryPolicy {
+      max_attempts: default_max_attempts(),
+      initial_delay_ms: default_initial_delay_ms(),
+      max_delay_ms: default_max_delay_ms(),
+      multiplier: default_multiplier(),
+      jitter: default_jitter(),
+    }
+  }
+}
+
+impl RetryPolicy {
+  /// Delay to wait before the attempt following `attempt` (starting at 1)
+  pub fn get_delay(&self, attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1) as i32;
+    let delay = (self.initial_delay_ms as f64 * self.multiplier.powi(exponent))
+      .min(self.max_delay_ms as f64) as u64;
+
+    let delay = if self.jitter && delay > 1 {
+      delay / 2 + rand::thread_rng().gen_range(0, delay / 2 + 1)
+    } else {
+      delay
+    };
+
+    Duration::from_millis(delay)
+  }
+}
+
+/// Tells whether a request can safely be sent more than once
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Idempotency {
+  Idempotent,
+  NonIdempotent,
+}
+
+/// HTTP client shared by the gateway and the publication calls
+#[derive(Clone, Debug)]
+pub struct HttpClient {
+  client: Client,
+  retry_policy: RetryPolicy,
+  stop_signal: StopSignal,
+}
+
+impl HttpClient {
+  pub fn new(retry_policy: RetryPolicy) -> Result<Self, String> {
+    let client = Client::builder()
+      .build()
+      .map_err(|error| format!("Unable to create HTTP client: {}", error))?;
+
+    Ok(HttpClient {
+      client,
+      retry_policy,
+      stop_signal: StopSignal::default(),
+    })
+  }
+
+  /// Stop signal interrupting the waits between attempts
+  pub fn with_stop_signal(mut self, stop_signal: &StopSignal) -> Self {
+    self.stop_signal = stop_signal.clone();
+    self
+  }
+
+  pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+    self.client.get(url)
+  }
+
+  pub fn post<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+    self.client.post(url)
+  }
+
+  pub fn patch<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+    self.client.patch(url)
+  }
+
+  pub fn put<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+    self.client.put(url)
+  }
+
+  pub fn delete<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+    self.client.delete(url)
+  }
+
+  /// Send the request, retrying transient failures when the request is idempotent.
+  ///
+  /// Responses with a non-transient status are returned as is, the caller is in charge of
+  /// handling them.
+  pub fn send(
+    &self,
+    request: RequestBuilder,
+    idempotency: Idempotency,
+  ) -> Result<Response, String> {
+    let max_attempts = if idempotency == Idempotency::Idempotent {
+      self.retry_policy.max_attempts.max(1)
+    } else {
+      1
+    };
+
+    let mut attempt = 1;
+    loop {
+      let request = request
+        .try_clone()
+        .ok_or_else(|| "Unable to clone HTTP request".to_string())?
+        .build()
+        .map_err(|error| format!("Invalid HTTP request: {}", error))?;
+
+      let description = format!("{} {}", request.method(), request.url());
+      info!("{} (attempt {}/{})", description, attempt, max_attempts);
+
+      let delay = match self.client.execute(request) {
+        Ok(response) => {
+          let status = response.status();
+          if !is_transient_status(status) || attempt >= max_attempts {
+            return Ok(response);
+          }
+          error!("{} returned status {}", description, status);
+          // The server delay is capped like the backoff one, not to hang the worker
+          let max_delay = Duration::from_millis(self.retry_policy.max_delay_ms);
+          get_retry_after(&response)
+            .map(|delay| delay.min(max_delay))
+            .unwrap_or_else(|| self.retry_policy.get_delay(attempt))
+        }
+        Err(error) => {
+          error!("{} failed: {}", description, error);
+          if attempt >= max_attempts {
+            return Err(error.to_string());
+          }
+          self.retry_policy.get_delay(attempt)
+        }
+      };
+
+      info!("Retry {} in {} ms", description, delay.as_millis());
+      if !self.sleep(delay) {
+        return Err(format!("Stop requested before retrying {}", description));
+      }
+      attempt += 1;
+    }
+  }
+}
+
+impl HttpClient {
+  /// Sleep for the delay, in short slices to return `false` as soon as a stop is requested
+  fn sleep(&self, delay: Duration) -> bool {
+    let wake_up = Instant::now() + delay;
+    while !self.stop_signal.is_raised() {
+      let now = Instant::now();
+      if now >= wake_up {
+        return true;
+      }
+      thread::sleep((wake_up - now).min(Duration::from_millis(100)));
+    }
+    false
+  }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+  matches!(
+    status,
+    StatusCode::REQUEST_TIMEOUT
+      | StatusCode::TOO_MANY_REQUESTS
+      | StatusCode::INTERNAL_SERVER_ERROR
+      | StatusCode::BAD_GATEWAY
+      | StatusCode::SERVICE_UNAVAILABLE
+      | StatusCode::GATEWAY_TIMEOUT
+  )
+}
+
+fn get_retry_after(response: &Response) -> Option<Duration> {
+  response
+    .headers()
+    .get(RETRY_AFTER)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| parse_retry_after(value, SystemTime::now()))
+}
+
+/// Parse a `Retry-After` header value, given either as delay seconds or as an HTTP date
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+  if let Ok(seconds) = value.trim().parse::<u64>() {
+    return Some(Duration::from_secs(seconds));
+  }
+
+  let date = httpdate::parse_http_date(value.trim()).ok()?;
+  Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// N-Triples published by `test_event`
+#[cfg(test)]
+pub(crate) const TEST_RDF: &str = "<http://resources.idfrancetv.fr/medias/99787afd> <http://purl.org/dc/elements/1.1/title> \"Title\" .\n";
+
+/// Graph of `TEST_RDF`, to be borrowed by `test_event`
+#[cfg(test)]
+pub(crate) fn test_graph() -> rdf::graph::Graph {
+  crate::convert::read_n_triples(TEST_RDF).unwrap()
+}
+
+/// `push_rdf_infos` event inserting `TEST_RDF` for the video `99787afd`
+#[cfg(test)]
+pub(crate) fn test_event(graph: &rdf::graph::Graph) -> crate::publisher::Event<'_> {
+  crate::publisher::Event {
+    reference: Some("99787afd"),
+    kind: crate::publisher::EventKind::Insert,
+    name: "push_rdf_infos",
+    graph,
+    rdf: TEST_RDF,
+    n_triples: true,
+    progression: crate::progression::ProgressionRange::publication(0, 1),
+  }
+}
+
+/// Stand-in HTTP server answering `status` to each request, and returning the requests it
+/// received (request line, headers and body) once `count` of them are served
+#[cfg(test)]
+pub(crate) fn start_test_server(
+  count: usize,
+  status: u16,
+) -> (String, std::thread::JoinHandle<Vec<String>>) {
+  let response = format!("HTTP/1.1 {} Status\r\nContent-Length: 0\r\n", status);
+  start_test_server_with_responses(vec![response; count])
+}
+
+/// Stand-in HTTP server answering the responses (status line and headers) in order, with a
+/// body after an empty line if any
+#[cfg(test)]
+pub(crate) fn start_test_server_with_responses(
+  responses: Vec<String>,
+) -> (String, std::thread::JoinHandle<Vec<String>>) {
+  use std::io::{BufRead, BufReader, Read, Write};
+  use std::net::TcpListener;
+
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let address = format!("http://{}", listener.local_addr().unwrap());
+
+  let handle = std::thread::spawn(move || {
+    let mut requests = vec![];
+    for (response, stream) in responses.into_iter().zip(listener.incoming()) {
+      let mut stream = stream.unwrap();
+      let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+      let mut request = String::new();
+      let mut content_length = 0;
+      loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line == "\r\n" {
+          break;
+        }
+        let lowercase = line.to_lowercase();
+        if let Some(length) = lowercase.strip_prefix("content-length:") {
+          content_length = length.trim().parse().unwrap();
+        }
+        request.push_str(&line);
+      }
+
+      let mut body = vec![0; content_length];
+      reader.read_exact(&mut body).unwrap();
+      request.push_str(&String::from_utf8(body).unwrap());
+      requests.push(request);
+
+      let response = match response.find("\r\n\r\n") {
+        Some(index) => format!(
+          "{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+          &response[..index],
+          response.len() - index - 4,
+          &response[index + 4..]
+        ),
+        None => format!("{}Connection: close\r\n\r\n", response),
+      };
+      stream.write_all(response.as_bytes()).unwrap();
+    }
+    requests
+  });
+
+  (address, handle)
+}
+
+#[test]
+fn test_retry_policy_delay() {
+  let policy = RetryPolicy {
+    max_attempts: 5,
+    initial_delay_ms: 100,
+    max_delay_ms: 1000,
+    multiplier: 2.0,
+    jitter: false,
+  };
+
+  assert_eq!(policy.get_delay(1), Duration::from_millis(100));
+  assert_eq!(policy.get_delay(3), Duration::from_millis(400));
+  assert_eq!(policy.get_delay(10), Duration::from_millis(1000));
+
+  let policy = RetryPolicy {
+    jitter: true,
+    ..policy
+  };
+  let delay = policy.get_delay(3);
+  assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400));
+}
+
+#[test]
+fn test_parse_retry_after() {
+  let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+
+  assert_eq!(
+    parse_retry_after("120", now),
+    Some(Duration::from_secs(120))
+  );
+  assert_eq!(
+    parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+    Some(Duration::from_secs(30))
+  );
+  assert_eq!(parse_retry_after("soon", now), None);
+}
+
+#[test]
+fn test_retry_after_capped() {
+  let (address, server) = start_test_server_with_responses(vec![
+    "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 86400\r\nContent-Length: 0\r\n".to_string(),
+    "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n".to_string(),
+  ]);
+  let retry_policy = RetryPolicy {
+    max_delay_ms: 10,
+    ..Default::default()
+  };
+  let http_client = HttpClient::new(retry_policy).unwrap();
+
+  let start = Instant::now();
+  let response = http_client
+    .send(http_client.get(&address), Idempotency::Idempotent)
+    .unwrap();
+  assert_eq!(response.status(), StatusCode::OK);
+  assert!(start.elapsed() < Duration::from_secs(5));
+  server.join().unwrap();
+
+  // A stop interrupts the wait
+  let stop_signal = StopSignal::default();
+  stop_signal.raise();
+  let (address, server) = start_test_server(1, 503);
+  let http_client = HttpClient::new(RetryPolicy::default())
+    .unwrap()
+    .with_stop_signal(&stop_signal);
+  assert!(http_client
+    .send(http_client.get(&address), Idempotency::Idempotent)
+    .is_err());
+  server.join().unwrap();
+}
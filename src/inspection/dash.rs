@@ -0,0 +1,222 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::resource_model::{AudioTrack, Format, ResourceBuilder, VideoTrack};
+
+/// Description of a DASH manifest (MPD)
+#[derive(Debug, Default, PartialEq)]
+pub struct DashManifest {
+  /// `mediaPresentationDuration`, as an ISO 8601 duration
+  pub duration: Option<String>,
+  pub representations: Vec<Representation>,
+}
+
+/// Representation of an adaptation set, with the attributes inherited from the adaptation set
+#[derive(Debug, Default, PartialEq)]
+pub struct Representation {
+  pub id: Option<String>,
+  pub content_type: Option<String>,
+  pub mime_type: Option<String>,
+  pub codecs: Option<String>,
+  pub bandwidth: Option<u64>,
+  pub width: Option<u16>,
+  pub height: Option<u16>,
+  pub frame_rate: Option<f64>,
+  pub sample_rate: Option<u32>,
+  pub lang: Option<String>,
+}
+
+pub fn read_manifest(path: &Path) -> Result<DashManifest, String> {
+  let content = fs::read_to_string(path)
+    .map_err(|error| format!("Unable to read {}: {}", path.display(), error))?;
+  parse_manifest(&content)
+}
+
+pub fn parse_manifest(content: &str) -> Result<DashManifest, String> {
+  let mut manifest = DashManifest::default();
+  let mut adaptation_set: Option<HashMap<String, String>> = None;
+
+  for event in EventReader::from_str(content) {
+    match event.map_err(|error| format!("Invalid DASH manifest: {}", error))? {
+      XmlEvent::StartElement {
+        name, attributes, ..
+      } => {
+        let attributes: HashMap<String, String> = attributes
+          .into_iter()
+          .map(|attribute| (attribute.name.local_name, attribute.value))
+          .collect();
+
+        match name.local_name.as_str() {
+          "MPD" => {
+            manifest.duration = attributes.get("mediaPresentationDuration").cloned();
+          }
+          "AdaptationSet" => {
+            adaptation_set = Some(attributes);
+          }
+          "Representation" => {
+            let mut inherited = adaptation_set.clone().unwrap_or_default();
+            inherited.extend(attributes);
+            manifest
+              .representations
+              .push(Representation::from(inherited));
+          }
+          _ => {}
+        }
+      }
+      XmlEvent::EndElement { name } if name.local_name == "AdaptationSet" => {
+        adaptation_set = None;
+      }
+      _ => {}
+    }
+  }
+
+  Ok(manifest)
+}
+
+impl From<HashMap<String, String>> for Representation {
+  fn from(attributes: HashMap<String, String>) -> Self {
+    let mime_type = attributes.get("mimeType").cloned();
+    let content_type = attributes.get("contentType").cloned().or_else(|| {
+      mime_type
+        .as_ref()
+        .and_then(|mime_type| mime_type.split('/').next())
+        .map(|kind| kind.to_string())
+    });
+
+    Representation {
+      id: attributes.get("id").cloned(),
+      content_type,
+      mime_type,
+      codecs: attributes.get("codecs").cloned(),
+      bandwidth: attributes
+        .get("bandwidth")
+        .and_then(|value| value.parse().ok()),
+      width: attributes.get("width").and_then(|value| value.parse().ok()),
+      height: attributes
+        .get("height")
+        .and_then(|value| value.parse().ok()),
+      frame_rate: attributes
+        .get("frameRate")
+        .and_then(|value| parse_frame_rate(value)),
+      sample_rate: attributes
+        .get("audioSamplingRate")
+        .and_then(|value| value.parse().ok()),
+      lang: attributes.get("lang").cloned(),
+    }
+  }
+}
+
+/// Parse a frame rate given as a number or as a fraction, like `30000/1001`
+fn parse_frame_rate(value: &str) -> Option<f64> {
+  let mut parts = value.splitn(2, '/');
+  let numerator: f64 = parts.next()?.trim().parse().ok()?;
+  match parts.next() {
+    Some(denominator) => {
+      let denominator: f64 = denominator.trim().parse().ok()?;
+      if denominator == 0.0 {
+        None
+      } else {
+        Some(numerator / denominator)
+      }
+    }
+    None => Some(numerator),
+  }
+}
+
+impl Representation {
+  /// Resource of the representation, with its video or audio track
+  pub fn to_resource_builder(&self) -> ResourceBuilder {
+    let mime_type = self
+      .mime_type
+      .clone()
+      .unwrap_or_else(|| "application/mp4".to_string());
+    let kind = self
+      .content_type
+      .clone()
+      .unwrap_or_else(|| "application".to_string());
+
+    let format = Format {
+      id: mime_type.replace('/', "-"),
+      label: mime_type.clone(),
+      kind: kind.clone(),
+      mime_type,
+    };
+
+    let bitrate_kbps = self.bandwidth.map(|bandwidth| bandwidth / 1000);
+    let mut builder = ResourceBuilder::new(format);
+    if let Some(bitrate_kbps) = bitrate_kbps {
+      builder = builder.bitrate_kbps(bitrate_kbps);
+    }
+    if let Some(ref lang) = self.lang {
+      builder = builder.lang(lang);
+    }
+
+    match kind.as_str() {
+      "video" => {
+        if let (Some(width), Some(height)) = (self.width, self.height) {
+          builder = builder.dimensions(width, height);
+        }
+        builder.video_track(VideoTrack {
+          codec_rfc6381: self.codecs.clone(),
+          bitrate_kbps: bitrate_kbps.unwrap_or_default(),
+          width: self.width.unwrap_or_default(),
+          height: self.height.unwrap_or_default(),
+          frame_rate_fps: self
+            .frame_rate
+            .map(|frame_rate| frame_rate.round() as u8)
+            .unwrap_or_default(),
+        })
+      }
+      "audio" => builder.audio_track(AudioTrack {
+        codec_rfc6381: self.codecs.clone(),
+        bitrate_kbps: bitrate_kbps.unwrap_or_default(),
+        sample_rate_hz: self.sample_rate.unwrap_or_default(),
+        lang: self.lang.clone().unwrap_or_default(),
+        channels: None,
+      }),
+      _ => builder,
+    }
+  }
+}
+
+#[test]
+fn test_parse_manifest() {
+  let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<MPD xmlns="urn:mpeg:dash:schema:mpd:2011" type="static" mediaPresentationDuration="PT1M30.5S">
+  <Period id="0">
+    <AdaptationSet mimeType="video/mp4" frameRate="30000/1001" segmentAlignment="true">
+      <Representation id="video_1" codecs="avc1.64001f" bandwidth="1500000" width="1280" height="720"/>
+      <Representation id="video_2" codecs="avc1.640028" bandwidth="3000000" width="1920" height="1080" frameRate="25"/>
+    </AdaptationSet>
+    <AdaptationSet mimeType="audio/mp4" lang="fr" audioSamplingRate="96000">
+      <Representation id="audio_fr" codecs="mp4a.40.2" bandwidth="128000">
+        <AudioChannelConfiguration schemeIdUri="urn:mpeg:dash:23003:3:audio_channel_configuration:2011" value="2"/>
+      </Representation>
+    </AdaptationSet>
+  </Period>
+</MPD>"#;
+
+  let manifest = parse_manifest(content).unwrap();
+  assert_eq!(manifest.duration, Some("PT1M30.5S".to_string()));
+  assert_eq!(manifest.representations.len(), 3);
+
+  let video = &manifest.representations[0];
+  assert_eq!(video.content_type, Some("video".to_string()));
+  assert_eq!(video.codecs, Some("avc1.64001f".to_string()));
+  assert_eq!(video.bandwidth, Some(1_500_000));
+  assert_eq!((video.width, video.height), (Some(1280), Some(720)));
+  assert_eq!(video.frame_rate.map(|rate| rate.round()), Some(30.0));
+  assert_eq!(manifest.representations[1].frame_rate, Some(25.0));
+
+  let audio = &manifest.representations[2];
+  assert_eq!(audio.content_type, Some("audio".to_string()));
+  assert_eq!(audio.lang, Some("fr".to_string()));
+  assert_eq!(audio.sample_rate, Some(96000));
+
+  let resource = audio.to_resource_builder().build();
+  assert_eq!(resource.format.id, "audio-mp4");
+  assert_eq!(resource.bitrate_kbps, Some(128));
+  assert_eq!(resource.audio_tracks[0].sample_rate_hz, 96000);
+  assert_eq!(resource.audio_tracks[0].lang, "fr");
+}
@@ -0,0 +1,332 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::resource_model::{AudioTrack, Format, ResourceBuilder, VideoTrack};
+
+/// Description of an HLS playlist
+#[derive(Debug, Default, PartialEq)]
+pub struct Playlist {
+  pub variants: Vec<VariantStream>,
+  pub media: Vec<Media>,
+  /// Sum of the segment durations of a complete media playlist, in seconds
+  pub duration_secs: Option<f64>,
+}
+
+/// Variant stream of a master playlist (`EXT-X-STREAM-INF`)
+#[derive(Debug, Default, PartialEq)]
+pub struct VariantStream {
+  pub uri: String,
+  pub bandwidth: Option<u64>,
+  pub resolution: Option<(u16, u16)>,
+  pub frame_rate: Option<f64>,
+  pub codecs: Vec<String>,
+  pub audio: Option<String>,
+  pub subtitles: Option<String>,
+}
+
+/// Alternative rendition of a master playlist (`EXT-X-MEDIA`)
+#[derive(Debug, Default, PartialEq)]
+pub struct Media {
+  pub kind: String,
+  pub group_id: String,
+  pub name: Option<String>,
+  pub language: Option<String>,
+  pub uri: Option<String>,
+}
+
+pub fn read_playlist(path: &Path) -> Result<Playlist, String> {
+  let content = fs::read_to_string(path)
+    .map_err(|error| format!("Unable to read {}: {}", path.display(), error))?;
+  parse_playlist(&content)
+}
+
+pub fn parse_playlist(content: &str) -> Result<Playlist, String> {
+  let mut lines = content
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty());
+
+  if lines.next() != Some("#EXTM3U") {
+    return Err("Invalid HLS playlist: missing #EXTM3U header".to_string());
+  }
+
+  let mut playlist = Playlist::default();
+  let mut stream_inf: Option<HashMap<String, String>> = None;
+  let mut segments_duration = 0.0;
+  let mut end_list = false;
+
+  for line in lines {
+    if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+      stream_inf = Some(parse_attributes(attributes));
+    } else if let Some(attributes) = line.strip_prefix("#EXT-X-MEDIA:") {
+      playlist
+        .media
+        .push(Media::from(parse_attributes(attributes)));
+    } else if let Some(duration) = line.strip_prefix("#EXTINF:") {
+      let duration = duration.split(',').next().unwrap_or_default();
+      segments_duration += duration.trim().parse::<f64>().unwrap_or_default();
+    } else if line == "#EXT-X-ENDLIST" {
+      end_list = true;
+    } else if !line.starts_with('#') {
+      if let Some(attributes) = stream_inf.take() {
+        playlist.variants.push(VariantStream::new(line, attributes));
+      }
+    }
+  }
+
+  if end_list && playlist.variants.is_empty() {
+    playlist.duration_secs = Some(segments_duration);
+  }
+
+  Ok(playlist)
+}
+
+/// Parse an attribute list, like `BANDWIDTH=1280000,CODECS="avc1.4d401f,mp4a.40.2"`
+fn parse_attributes(attributes: &str) -> HashMap<String, String> {
+  let mut result = HashMap::new();
+  let mut rest = attributes.trim();
+
+  while !rest.is_empty() {
+    let (name, value) = match rest.find('=') {
+      Some(position) => (&rest[..position], &rest[position + 1..]),
+      None => break,
+    };
+
+    let (value, next) = if let Some(quoted) = value.strip_prefix('"') {
+      let end = quoted.find('"').unwrap_or(quoted.len());
+      let next = quoted.get(end + 1..).unwrap_or_default();
+      (&quoted[..end], next)
+    } else {
+      let end = value.find(',').unwrap_or(value.len());
+      (&value[..end], &value[end..])
+    };
+
+    result.insert(name.trim().to_string(), value.to_string());
+    rest = next.trim_start_matches(',').trim();
+  }
+
+  result
+}
+
+impl VariantStream {
+  fn new(uri: &str, attributes: HashMap<String, String>) -> Self {
+    let resolution = attributes.get("RESOLUTION").and_then(|resolution| {
+      let mut dimensions = resolution.splitn(2, 'x');
+      let width = dimensions.next()?.parse().ok()?;
+      let height = dimensions.next()?.parse().ok()?;
+      Some((width, height))
+    });
+
+    VariantStream {
+      uri: uri.to_string(),
+      bandwidth: attributes
+        .get("AVERAGE-BANDWIDTH")
+        .or_else(|| attributes.get("BANDWIDTH"))
+        .and_then(|bandwidth| bandwidth.parse().ok()),
+      resolution,
+      frame_rate: attributes
+        .get("FRAME-RATE")
+        .and_then(|frame_rate| frame_rate.parse().ok()),
+      codecs: attributes
+        .get("CODECS")
+        .map(|codecs| {
+          codecs
+            .split(',')
+            .map(|codec| codec.trim().to_string())
+            .collect()
+        })
+        .unwrap_or_default(),
+      audio: attributes.get("AUDIO").cloned(),
+      subtitles: attributes.get("SUBTITLES").cloned(),
+    }
+  }
+
+  fn get_video_codec(&self) -> Option<&String> {
+    self.codecs.iter().find(|codec| {
+      ["avc1", "avc3", "hvc1", "hev1", "vp09", "av01", "mp4v"]
+        .iter()
+        .any(|prefix| codec.starts_with(prefix))
+    })
+  }
+
+  fn get_audio_codec(&self) -> Option<&String> {
+    self.codecs.iter().find(|codec| {
+      ["mp4a", "ac-3", "ec-3", "opus", "flac"]
+        .iter()
+        .any(|prefix| codec.starts_with(prefix))
+    })
+  }
+}
+
+impl From<HashMap<String, String>> for Media {
+  fn from(attributes: HashMap<String, String>) -> Self {
+    Media {
+      kind: attributes.get("TYPE").cloned().unwrap_or_default(),
+      group_id: attributes.get("GROUP-ID").cloned().unwrap_or_default(),
+      name: attributes.get("NAME").cloned(),
+      language: attributes.get("LANGUAGE").cloned(),
+      uri: attributes.get("URI").cloned(),
+    }
+  }
+}
+
+impl Playlist {
+  /// Resources of the variant streams and of the audio and subtitles renditions having their
+  /// own playlist, in the `format` of the master playlist
+  pub fn to_resource_builders(&self, format: &Format) -> Vec<ResourceBuilder> {
+    let mut builders = vec![];
+
+    for variant in &self.variants {
+      let mut builder = ResourceBuilder::new(format.clone()).filename(&variant.uri);
+      let bitrate_kbps = variant.bandwidth.map(|bandwidth| bandwidth / 1000);
+      if let Some(bitrate_kbps) = bitrate_kbps {
+        builder = builder.bitrate_kbps(bitrate_kbps);
+      }
+
+      if variant.resolution.is_some() || variant.get_video_codec().is_some() {
+        let (width, height) = variant.resolution.unwrap_or_default();
+        if variant.resolution.is_some() {
+          builder = builder.dimensions(width, height);
+        }
+        builder = builder.video_track(VideoTrack {
+          codec_rfc6381: variant.get_video_codec().cloned(),
+          bitrate_kbps: 0,
+          width,
+          height,
+          frame_rate_fps: variant
+            .frame_rate
+            .map(|frame_rate| frame_rate.round() as u8)
+            .unwrap_or_default(),
+        });
+      }
+
+      // Audio of the variant stream, when not delivered in separate renditions
+      if let Some(audio_codec) = variant.get_audio_codec() {
+        let group = self.get_group("AUDIO", &variant.audio);
+        if group.is_empty() {
+          builder = builder.audio_track(new_audio_track(audio_codec, None));
+        }
+        for media in group.iter().filter(|media| media.uri.is_none()) {
+          builder = builder.audio_track(new_audio_track(audio_codec, media.language.as_ref()));
+        }
+      }
+
+      builders.push(builder);
+    }
+
+    for media in &self.media {
+      let uri = match media.uri {
+        Some(ref uri) => uri,
+        None => continue,
+      };
+
+      let mut builder = ResourceBuilder::new(format.clone()).filename(uri);
+      if let Some(ref language) = media.language {
+        builder = builder.lang(language);
+      }
+
+      match media.kind.as_str() {
+        "AUDIO" => {
+          let audio_codec = self
+            .variants
+            .iter()
+            .filter(|variant| variant.audio.as_ref() == Some(&media.group_id))
+            .find_map(|variant| variant.get_audio_codec());
+          builder = builder.audio_track(AudioTrack {
+            codec_rfc6381: audio_codec.cloned(),
+            bitrate_kbps: 0,
+            sample_rate_hz: 0,
+            lang: media.language.clone().unwrap_or_default(),
+            channels: None,
+          });
+        }
+        "SUBTITLES" => {}
+        _ => continue,
+      }
+
+      builders.push(builder);
+    }
+
+    builders
+  }
+
+  fn get_group(&self, kind: &str, group_id: &Option<String>) -> Vec<&Media> {
+    self
+      .media
+      .iter()
+      .filter(|media| media.kind == kind && Some(&media.group_id) == group_id.as_ref())
+      .collect()
+  }
+}
+
+fn new_audio_track(codec: &str, language: Option<&String>) -> AudioTrack {
+  AudioTrack {
+    codec_rfc6381: Some(codec.to_string()),
+    bitrate_kbps: 0,
+    sample_rate_hz: 0,
+    lang: language.cloned().unwrap_or_default(),
+    channels: None,
+  }
+}
+
+#[test]
+fn test_parse_master_playlist() {
+  let content = r#"#EXTM3U
+#EXT-X-VERSION:4
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aac",NAME="Français",LANGUAGE="fr",DEFAULT=YES,URI="audio/fr.m3u8"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aac",NAME="Audiodescription",LANGUAGE="qad",URI="audio/qad.m3u8"
+#EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID="subs",NAME="Français",LANGUAGE="fr",URI="subtitles/fr.m3u8"
+#EXT-X-STREAM-INF:BANDWIDTH=1280000,RESOLUTION=1280x720,FRAME-RATE=25.000,CODECS="avc1.4d401f,mp4a.40.2",AUDIO="aac",SUBTITLES="subs"
+video/720p.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=640000,CODECS="avc1.42001e,mp4a.40.2"
+video/360p.m3u8
+"#;
+
+  let playlist = parse_playlist(content).unwrap();
+  assert_eq!(playlist.variants.len(), 2);
+  assert_eq!(playlist.media.len(), 3);
+  assert_eq!(playlist.duration_secs, None);
+
+  let variant = &playlist.variants[0];
+  assert_eq!(variant.uri, "video/720p.m3u8");
+  assert_eq!(variant.bandwidth, Some(1_280_000));
+  assert_eq!(variant.resolution, Some((1280, 720)));
+  assert_eq!(variant.codecs, vec!["avc1.4d401f", "mp4a.40.2"]);
+  assert_eq!(variant.audio, Some("aac".to_string()));
+  assert_eq!(variant.subtitles, Some("subs".to_string()));
+  assert_eq!(playlist.media[1].language, Some("qad".to_string()));
+
+  let format = Format {
+    id: "playlist-hls".to_string(),
+    label: "playlist/hls".to_string(),
+    kind: "playlist".to_string(),
+    mime_type: "application/x-mpegURL".to_string(),
+  };
+  let resources: Vec<_> = playlist
+    .to_resource_builders(&format)
+    .into_iter()
+    .map(|builder| builder.build())
+    .collect();
+  assert_eq!(resources.len(), 5);
+
+  // Audio is delivered in separate renditions
+  assert_eq!(resources[0].video_tracks[0].frame_rate_fps, 25);
+  assert!(resources[0].audio_tracks.is_empty());
+  // Audio is muxed in the variant stream
+  assert_eq!(resources[1].audio_tracks.len(), 1);
+
+  assert_eq!(resources[3].lang, Some("qad".to_string()));
+  assert_eq!(
+    resources[3].audio_tracks[0].codec_rfc6381,
+    Some("mp4a.40.2".to_string())
+  );
+  assert_eq!(resources[4].filename, Some("subtitles/fr.m3u8".to_string()));
+}
+
+#[test]
+fn test_parse_media_playlist() {
+  let content = "#EXTM3U\n#EXT-X-TARGETDURATION:10\n#EXTINF:10.0,\nsegment0.ts\n#EXTINF:4.5,\nsegment1.ts\n#EXT-X-ENDLIST\n";
+
+  let playlist = parse_playlist(content).unwrap();
+  assert!(playlist.variants.is_empty());
+  assert_eq!(playlist.duration_secs, Some(14.5));
+}
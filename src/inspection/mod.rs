@@ -0,0 +1,146 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use mcai_worker_sdk::{info, warn};
+
+use crate::resource_model::{Format, ResourceBuilder, SubtitleStats};
+
+pub mod dash;
+pub mod hls;
+pub mod mp4;
+pub mod ttml;
+pub mod webvtt;
+
+/// Timed text cue, with its times in seconds
+#[derive(Debug, PartialEq)]
+pub struct Cue {
+  pub begin: f64,
+  pub end: f64,
+  pub text: String,
+}
+
+/// Cues of a subtitles document
+#[derive(Debug, Default, PartialEq)]
+pub struct TimedText {
+  /// Language declared by the document
+  pub lang: Option<String>,
+  pub cues: Vec<Cue>,
+  /// Whether the document uses subtitles for the deaf and hard of hearing conventions
+  pub sdh: bool,
+}
+
+/// Read the cues of a local subtitles file, in TTML or WebVTT format
+pub fn read_timed_text(format: &Format, path: &Path) -> Result<TimedText, String> {
+  match format.mime_type.as_str() {
+    "application/xml+ttml" => ttml::read_document(path),
+    "text/vtt" => webvtt::read_document(path),
+    mime_type => Err(format!(
+      "Unable to read cues of {}: unsupported format {}",
+      path.display(),
+      mime_type
+    )),
+  }
+}
+
+/// Local file of an input path, given either as a path or as a `file://` URL
+pub fn get_local_path(path: &str) -> Option<PathBuf> {
+  let path = Path::new(path.strip_prefix("file://").unwrap_or(path));
+  if path.is_file() {
+    Some(path.to_path_buf())
+  } else {
+    None
+  }
+}
+
+/// Describe the content of a local file, according to its format.
+///
+/// Renditions found in manifests are stored in `storage`, like the manifest itself.
+pub fn inspect(
+  builder: ResourceBuilder,
+  format: &Format,
+  path: &Path,
+  storage: &str,
+) -> ResourceBuilder {
+  let inspection = match format.mime_type.as_str() {
+    "application/dash+xml" => {
+      info!("Inspect DASH manifest {}", path.display());
+      dash::read_manifest(path).map(|manifest| {
+        let renditions = manifest
+          .representations
+          .iter()
+          .map(|representation| representation.to_resource_builder())
+          .collect();
+        (renditions, manifest.duration)
+      })
+    }
+    "application/x-mpegURL" => {
+      info!("Inspect HLS playlist {}", path.display());
+      hls::read_playlist(path).map(|playlist| {
+        let duration = playlist.duration_secs.map(format_duration);
+        (playlist.to_resource_builders(format), duration)
+      })
+    }
+    "video/mp4" => {
+      info!("Inspect MP4 file {}", path.display());
+      return match mp4::read_file(path) {
+        Ok(movie) => {
+          let file_size = fs::metadata(path).map(|metadata| metadata.len()).ok();
+          movie.describe(builder, file_size)
+        }
+        Err(error) => {
+          warn!("Unable to inspect {}: {}", path.display(), error);
+          builder
+        }
+      };
+    }
+    "application/xml+ttml" | "text/vtt" => {
+      info!("Inspect subtitles {}", path.display());
+      return match read_timed_text(format, path) {
+        Ok(document) => {
+          let builder = builder.subtitle_stats(SubtitleStats::new(&document.cues, document.sdh));
+          match document.lang {
+            Some(ref lang) => builder.lang(lang),
+            None => builder,
+          }
+        }
+        Err(error) => {
+          warn!("Unable to inspect {}: {}", path.display(), error);
+          builder
+        }
+      };
+    }
+    _ => return builder,
+  };
+
+  match inspection {
+    Ok((renditions, duration)) => {
+      let renditions = renditions
+        .into_iter()
+        .map(|rendition| rendition.storage(storage).build())
+        .collect();
+      let builder = builder.renditions(renditions);
+      match duration {
+        Some(ref duration) => builder.duration(duration),
+        None => builder,
+      }
+    }
+    Err(error) => {
+      warn!("Unable to inspect {}: {}", path.display(), error);
+      builder
+    }
+  }
+}
+
+/// ISO 8601 duration of a number of seconds, like `PT90.5S`
+pub fn format_duration(seconds: f64) -> String {
+  format!("PT{}S", (seconds * 1000.0).round() / 1000.0)
+}
+
+#[test]
+fn test_format_duration() {
+  assert_eq!(format_duration(14.5), "PT14.5S");
+  assert_eq!(format_duration(3600.0), "PT3600S");
+  assert_eq!(format_duration(1.0 / 3.0), "PT0.333S");
+}
@@ -0,0 +1,562 @@
+use std::{
+  convert::TryInto,
+  fs::File,
+  io::{Read, Seek, SeekFrom},
+  path::Path,
+};
+
+use super::format_duration;
+#[cfg(test)]
+use crate::resource_model::Format;
+use crate::resource_model::{AudioTrack, ResourceBuilder, VideoTrack};
+
+/// Description of an ISO-BMFF (MP4) file, read from its `moov` box
+#[derive(Debug, Default, PartialEq)]
+pub struct Movie {
+  pub duration_secs: Option<f64>,
+  pub tracks: Vec<Track>,
+}
+
+/// Track of a movie, with the properties of its first sample entry
+#[derive(Debug, Default, PartialEq)]
+pub struct Track {
+  /// Handler type, like `vide` or `soun`
+  pub handler: String,
+  pub codec: Option<String>,
+  pub duration_secs: Option<f64>,
+  /// Size of the samples, in bytes
+  pub samples_size: u64,
+  pub lang: Option<String>,
+  pub width: u16,
+  pub height: u16,
+  pub frame_rate: Option<f64>,
+  pub channels: u16,
+  pub sample_rate: u32,
+}
+
+/// Read the `moov` box of a file, skipping media data
+pub fn read_file(path: &Path) -> Result<Movie, String> {
+  let error = |error: std::io::Error| format!("Unable to read {}: {}", path.display(), error);
+  let mut file = File::open(path).map_err(error)?;
+  let file_size = file.metadata().map_err(error)?.len();
+
+  let mut position = 0;
+  while position + 8 <= file_size {
+    let mut header = [0; 16];
+    file.read_exact(&mut header[..8]).map_err(error)?;
+    let mut header_size = 8;
+    let size = match u32::from_be_bytes(header[..4].try_into().unwrap()) {
+      0 => file_size - position,
+      1 => {
+        file.read_exact(&mut header[8..]).map_err(error)?;
+        header_size = 16;
+        u64::from_be_bytes(header[8..].try_into().unwrap())
+      }
+      size => u64::from(size),
+    };
+    if size < header_size || size > file_size - position {
+      return Err(format!("Invalid MP4 file: box of {} bytes", size));
+    }
+
+    if &header[4..8] == b"moov" {
+      let mut content = vec![0; (size - header_size) as usize];
+      file.read_exact(&mut content).map_err(error)?;
+      return parse_movie(&content);
+    }
+
+    position += size;
+    file.seek(SeekFrom::Start(position)).map_err(error)?;
+  }
+
+  Err("Invalid MP4 file: missing moov box".to_string())
+}
+
+/// Parse the content of a `moov` box
+pub fn parse_movie(content: &[u8]) -> Result<Movie, String> {
+  let mut movie = Movie::default();
+
+  for (kind, content) in Boxes::new(content) {
+    match kind {
+      b"mvhd" => {
+        let (timescale, duration) = read_time(content)?;
+        movie.duration_secs = to_seconds(duration, timescale);
+      }
+      b"trak" => movie.tracks.push(parse_track(content)?),
+      _ => {}
+    }
+  }
+
+  Ok(movie)
+}
+
+fn parse_track(content: &[u8]) -> Result<Track, String> {
+  let mut track = Track::default();
+  let mdia = find_box(content, &[b"mdia"]).unwrap_or_default();
+  let mut timescale = 0;
+
+  if let Some(mdhd) = find_box(mdia, &[b"mdhd"]) {
+    let (mdhd_timescale, duration) = read_time(mdhd)?;
+    timescale = mdhd_timescale;
+    track.duration_secs = to_seconds(duration, timescale);
+
+    let lang_offset = if mdhd.first() == Some(&1) { 32 } else { 20 };
+    track.lang = read_u16(mdhd, lang_offset).and_then(unpack_language);
+
+    if let Some(stts) = find_box(mdia, &[b"minf", b"stbl", b"stts"]) {
+      track.frame_rate = get_sample_rate(stts, timescale);
+    }
+  }
+
+  if let Some(hdlr) = find_box(mdia, &[b"hdlr"]) {
+    track.handler = hdlr
+      .get(8..12)
+      .map(|handler| String::from_utf8_lossy(handler).to_string())
+      .unwrap_or_default();
+  }
+
+  if let Some(stsz) = find_box(mdia, &[b"minf", b"stbl", b"stsz"]) {
+    track.samples_size = get_samples_size(stsz);
+  }
+
+  let stsd = find_box(mdia, &[b"minf", b"stbl", b"stsd"]).unwrap_or_default();
+  // Sample entries follow the version, flags and entry count
+  if let Some((kind, entry)) = stsd.get(8..).and_then(|entries| Boxes::new(entries).next()) {
+    let fourcc = String::from_utf8_lossy(kind).to_string();
+    match track.handler.as_str() {
+      "vide" => {
+        track.width = read_u16(entry, 24).unwrap_or_default();
+        track.height = read_u16(entry, 26).unwrap_or_default();
+        let avcc = entry
+          .get(78..)
+          .and_then(|boxes| find_box(boxes, &[b"avcC"]));
+        track.codec = Some(match avcc.and_then(|avcc| avcc.get(1..4)) {
+          Some(profile) => format!(
+            "{}.{:02x}{:02x}{:02x}",
+            fourcc, profile[0], profile[1], profile[2]
+          ),
+          None => fourcc,
+        });
+      }
+      "soun" => {
+        track.channels = read_u16(entry, 16).unwrap_or_default();
+        // Rates above 65535 Hz do not fit in the sample entry, they are the media timescale
+        track.sample_rate = match read_u16(entry, 24).unwrap_or_default() {
+          0 => timescale,
+          sample_rate => u32::from(sample_rate),
+        };
+        let esds = entry
+          .get(28..)
+          .and_then(|boxes| find_box(boxes, &[b"esds"]));
+        track.codec = Some(match esds.and_then(get_mpeg4_audio_codec) {
+          Some((object_type, audio_object_type)) => {
+            format!("{}.{:x}.{}", fourcc, object_type, audio_object_type)
+          }
+          None => fourcc,
+        });
+      }
+      _ => track.codec = Some(fourcc),
+    }
+  }
+
+  Ok(track)
+}
+
+/// Iterator over the boxes of a box content, as their type and content
+struct Boxes<'a> {
+  content: &'a [u8],
+}
+
+impl<'a> Boxes<'a> {
+  fn new(content: &'a [u8]) -> Self {
+    Boxes { content }
+  }
+}
+
+impl<'a> Iterator for Boxes<'a> {
+  type Item = (&'a [u8], &'a [u8]);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let size = read_u32(self.content, 0)? as usize;
+    let kind = self.content.get(4..8)?;
+    let (header_size, size) = match size {
+      0 => (8, self.content.len()),
+      1 => (16, read_u64(self.content, 8)? as usize),
+      size => (8, size),
+    };
+    if size < header_size || size > self.content.len() {
+      return None;
+    }
+
+    let content = &self.content[header_size..size];
+    self.content = &self.content[size..];
+    Some((kind, content))
+  }
+}
+
+/// Content of the box at the end of a path of box types
+fn find_box<'a>(content: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+  path.iter().try_fold(content, |content, kind| {
+    Boxes::new(content)
+      .find(|(box_kind, _)| box_kind == kind)
+      .map(|(_, content)| content)
+  })
+}
+
+fn read_u16(content: &[u8], offset: usize) -> Option<u16> {
+  let bytes = content.get(offset..offset + 2)?;
+  Some(u16::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u32(content: &[u8], offset: usize) -> Option<u32> {
+  let bytes = content.get(offset..offset + 4)?;
+  Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u64(content: &[u8], offset: usize) -> Option<u64> {
+  let bytes = content.get(offset..offset + 8)?;
+  Some(u64::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// Timescale and duration of a `mvhd` or `mdhd` box, following 64 bits creation and modification
+/// times in `version 1` boxes, or 32 bits ones otherwise
+fn read_time(content: &[u8]) -> Result<(u32, u64), String> {
+  let time = if content.first() == Some(&1) {
+    read_u32(content, 20).zip(read_u64(content, 24))
+  } else {
+    read_u32(content, 12).zip(read_u32(content, 16).map(u64::from))
+  };
+  time.ok_or_else(|| "Invalid MP4 file: truncated header box".to_string())
+}
+
+fn to_seconds(duration: u64, timescale: u32) -> Option<f64> {
+  if timescale == 0 || duration == 0 || duration == u64::from(u32::MAX) || duration == u64::MAX {
+    return None;
+  }
+  Some(duration as f64 / f64::from(timescale))
+}
+
+/// ISO 639-2 language packed as three 5 bits characters, `und` being ignored
+fn unpack_language(packed: u16) -> Option<String> {
+  let lang: String = [10, 5, 0]
+    .iter()
+    .map(|shift| char::from(((packed >> shift) & 0x1f) as u8 + 0x60))
+    .collect();
+  if lang == "und" || !lang.chars().all(|c| c.is_ascii_lowercase()) {
+    None
+  } else {
+    Some(lang)
+  }
+}
+
+/// Number of samples per second, from the time-to-sample table, unknown if its totals overflow
+fn get_sample_rate(stts: &[u8], timescale: u32) -> Option<f64> {
+  let entry_count = read_u32(stts, 4)? as usize;
+  let (mut samples, mut duration) = (0u64, 0u64);
+  for entry in 0..entry_count {
+    let count = u64::from(read_u32(stts, 8 + entry * 8)?);
+    let delta = u64::from(read_u32(stts, 12 + entry * 8)?);
+    samples = samples.checked_add(count)?;
+    duration = duration.checked_add(count.checked_mul(delta)?)?;
+  }
+  if duration == 0 {
+    return None;
+  }
+  Some(samples as f64 * f64::from(timescale) / duration as f64)
+}
+
+/// Total size of the samples, from the sample size table
+fn get_samples_size(stsz: &[u8]) -> u64 {
+  let sample_size = read_u32(stsz, 4).unwrap_or_default();
+  let sample_count = read_u32(stsz, 8).unwrap_or_default();
+  if sample_size != 0 {
+    return u64::from(sample_size) * u64::from(sample_count);
+  }
+  // The count is bounded by the entries of the box
+  stsz
+    .get(12..)
+    .unwrap_or_default()
+    .chunks_exact(4)
+    .take(sample_count as usize)
+    .map(|size| u64::from(u32::from_be_bytes(size.try_into().unwrap())))
+    .sum()
+}
+
+/// Object type indication and audio object type of an elementary stream descriptor
+fn get_mpeg4_audio_codec(esds: &[u8]) -> Option<(u8, u8)> {
+  // Descriptors follow the version and flags
+  let mut content = esds.get(4..)?;
+  let mut object_type = None;
+
+  while let Some((&tag, rest)) = content.split_first() {
+    // Descriptor size is encoded on up to 4 bytes, 7 bits each
+    let mut size = 0usize;
+    let mut rest = rest;
+    for _ in 0..4 {
+      let (&byte, next) = rest.split_first()?;
+      size = (size << 7) | usize::from(byte & 0x7f);
+      rest = next;
+      if byte & 0x80 == 0 {
+        break;
+      }
+    }
+    let descriptor = rest.get(..size)?;
+
+    match tag {
+      // ES descriptor, with optional fields given by its flags
+      0x03 => {
+        let flags = *descriptor.get(2)?;
+        let mut offset = 3;
+        if flags & 0x80 != 0 {
+          offset += 2;
+        }
+        if flags & 0x40 != 0 {
+          offset += 1 + usize::from(*descriptor.get(offset)?);
+        }
+        if flags & 0x20 != 0 {
+          offset += 2;
+        }
+        content = descriptor.get(offset..)?;
+        continue;
+      }
+      // Decoder config descriptor, containing the decoder specific info
+      0x04 => {
+        object_type = descriptor.first().copied();
+        content = descriptor.get(13..)?;
+        continue;
+      }
+      0x05 => return Some((object_type?, descriptor.first()? >> 3)),
+      _ => {}
+    }
+    content = rest.get(size..)?;
+  }
+
+  None
+}
+
+impl Movie {
+  /// Describe the tracks of the movie, `file_size` giving its overall bitrate
+  pub fn describe(&self, builder: ResourceBuilder, file_size: Option<u64>) -> ResourceBuilder {
+    let mut builder = builder;
+
+    if let Some(duration_secs) = self.duration_secs {
+      builder = builder.duration(&format_duration(duration_secs));
+      if let Some(file_size) = file_size {
+        builder = builder.bitrate_kbps(to_kbps(file_size, duration_secs));
+      }
+    }
+
+    for track in &self.tracks {
+      let bitrate_kbps = track
+        .duration_secs
+        .map(|duration_secs| to_kbps(track.samples_size, duration_secs))
+        .unwrap_or_default();
+
+      match track.handler.as_str() {
+        "vide" => {
+          if track.width > 0 && track.height > 0 {
+            builder = builder.dimensions(track.width, track.height);
+          }
+          builder = builder.video_track(VideoTrack {
+            codec_rfc6381: track.codec.clone(),
+            bitrate_kbps,
+            width: track.width,
+            height: track.height,
+            frame_rate_fps: track
+              .frame_rate
+              .map(|frame_rate| frame_rate.round() as u8)
+              .unwrap_or_default(),
+          });
+        }
+        "soun" => {
+          builder = builder.audio_track(AudioTrack {
+            codec_rfc6381: track.codec.clone(),
+            bitrate_kbps,
+            sample_rate_hz: track.sample_rate,
+            lang: track.lang.clone().unwrap_or_default(),
+            channels: Some(track.channels).filter(|channels| *channels > 0),
+          });
+        }
+        _ => {}
+      }
+    }
+
+    builder
+  }
+}
+
+fn to_kbps(size: u64, duration_secs: f64) -> u64 {
+  if duration_secs > 0.0 {
+    (size as f64 * 8.0 / duration_secs / 1000.0).round() as u64
+  } else {
+    0
+  }
+}
+
+#[cfg(test)]
+fn new_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+  let mut result = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+  result.extend_from_slice(kind);
+  result.extend_from_slice(content);
+  result
+}
+
+#[cfg(test)]
+fn new_track(handler: &[u8; 4], lang: u16, timescale: u32, sample_entry: Vec<u8>) -> Vec<u8> {
+  let concat = |parts: &[&[u8]]| parts.concat();
+
+  // 10 seconds of 1000 bytes samples, lasting 1/25 s each
+  let mdhd = concat(&[
+    &[0; 12],
+    &timescale.to_be_bytes(),
+    &(timescale * 10).to_be_bytes(),
+    &lang.to_be_bytes(),
+    &[0; 2],
+  ]);
+  let hdlr = concat(&[&[0; 8], handler, &[0; 13]]);
+  let stsd = concat(&[&[0, 0, 0, 0, 0, 0, 0, 1], &sample_entry]);
+  let stts = concat(&[
+    &[0, 0, 0, 0, 0, 0, 0, 1],
+    &250u32.to_be_bytes(),
+    &(timescale / 25).to_be_bytes(),
+  ]);
+  let stsz = concat(&[&[0; 4], &1000u32.to_be_bytes(), &250u32.to_be_bytes()]);
+
+  let stbl = concat(&[
+    &new_box(b"stsd", &stsd),
+    &new_box(b"stts", &stts),
+    &new_box(b"stsz", &stsz),
+  ]);
+  let minf = new_box(b"stbl", &stbl);
+  let mdia = concat(&[
+    &new_box(b"mdhd", &mdhd),
+    &new_box(b"hdlr", &hdlr),
+    &new_box(b"minf", &minf),
+  ]);
+  new_box(b"trak", &new_box(b"mdia", &mdia))
+}
+
+#[test]
+fn test_parse_movie() {
+  let avc1 = [
+    &[0; 24][..],
+    &1280u16.to_be_bytes(),
+    &720u16.to_be_bytes(),
+    &[0; 50],
+    &new_box(b"avcC", &[1, 0x4d, 0x40, 0x1f]),
+  ]
+  .concat();
+  let esds = [
+    &[0; 4][..],
+    &[0x03, 0x80, 0x80, 0x80, 0x16, 0, 1, 0],
+    &[0x04, 0x11, 0x40, 0x15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    &[0x05, 0x02, 0x11, 0x90],
+  ]
+  .concat();
+  let mp4a = [
+    &[0; 16][..],
+    &2u16.to_be_bytes(),
+    &[0; 6],
+    &48000u16.to_be_bytes(),
+    &[0; 2],
+    &new_box(b"esds", &esds),
+  ]
+  .concat();
+  // "fra" packed on 3 times 5 bits
+  let fra = (6 << 10) | (18 << 5) | 1;
+
+  let mvhd = [
+    &[0; 12][..],
+    &1000u32.to_be_bytes(),
+    &10000u32.to_be_bytes(),
+  ]
+  .concat();
+  let moov = [
+    new_box(b"mvhd", &mvhd),
+    new_track(b"vide", 0x55c4, 12800, new_box(b"avc1", &avc1)),
+    new_track(b"soun", fra, 48000, new_box(b"mp4a", &mp4a)),
+  ]
+  .concat();
+
+  let movie = parse_movie(&moov).unwrap();
+  assert_eq!(movie.duration_secs, Some(10.0));
+  assert_eq!(
+    movie.tracks,
+    vec![
+      Track {
+        handler: "vide".to_string(),
+        codec: Some("avc1.4d401f".to_string()),
+        duration_secs: Some(10.0),
+        samples_size: 250_000,
+        lang: None,
+        width: 1280,
+        height: 720,
+        frame_rate: Some(25.0),
+        channels: 0,
+        sample_rate: 0,
+      },
+      Track {
+        handler: "soun".to_string(),
+        codec: Some("mp4a.40.2".to_string()),
+        duration_secs: Some(10.0),
+        samples_size: 250_000,
+        lang: Some("fra".to_string()),
+        width: 0,
+        height: 0,
+        frame_rate: Some(25.0),
+        channels: 2,
+        sample_rate: 48000,
+      },
+    ]
+  );
+
+  let resource = movie
+    .describe(
+      ResourceBuilder::new(Format {
+        id: "video-mp4".to_string(),
+        label: "video/mp4".to_string(),
+        kind: "video".to_string(),
+        mime_type: "video/mp4".to_string(),
+      }),
+      Some(1_000_000),
+    )
+    .build();
+  assert_eq!(resource.duration, Some("PT10S".to_string()));
+  assert_eq!(resource.bitrate_kbps, Some(800));
+  assert_eq!(resource.width, Some(1280));
+  assert_eq!(resource.video_tracks[0].bitrate_kbps, 200);
+  assert_eq!(resource.audio_tracks[0].channels, Some(2));
+}
+
+#[test]
+fn test_sample_rate_overflow() {
+  let entry = [u32::MAX.to_be_bytes(), u32::MAX.to_be_bytes()].concat();
+  let mut stts = vec![0, 0, 0, 0, 0, 0, 0, 1];
+  stts.extend(&entry);
+  assert_eq!(get_sample_rate(&stts, 25), Some(25.0 / f64::from(u32::MAX)));
+
+  stts[7] = 2;
+  stts.extend(&entry);
+  assert_eq!(get_sample_rate(&stts, 25), None);
+}
+
+#[test]
+fn test_read_invalid_file() {
+  use std::fs;
+
+  let path = std::env::temp_dir().join(format!("rdf_worker_{}.mp4", uuid::Uuid::new_v4()));
+  // A moov box with a 64 bits size far beyond the end of the file
+  let content = [
+    &1u32.to_be_bytes()[..],
+    b"moov",
+    &(1u64 << 40).to_be_bytes(),
+  ]
+  .concat();
+  fs::write(&path, content).unwrap();
+  let result = read_file(&path);
+  fs::remove_file(&path).unwrap();
+  assert!(result.is_err());
+
+  // A sample size table announcing more samples than it holds
+  let stsz = [&[0; 8][..], &u32::MAX.to_be_bytes(), &1000u32.to_be_bytes()].concat();
+  assert_eq!(get_samples_size(&stsz), 1000);
+}
@@ -0,0 +1,236 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use xml::reader::{EventReader, XmlEvent};
+
+use super::{Cue, TimedText};
+
+/// Timing parameters of the document, used to convert frames and ticks
+struct TimeBase {
+  frame_rate: f64,
+  tick_rate: f64,
+}
+
+pub fn read_document(path: &Path) -> Result<TimedText, String> {
+  let content = fs::read_to_string(path)
+    .map_err(|error| format!("Unable to read {}: {}", path.display(), error))?;
+  parse_document(&content)
+}
+
+/// Parse the paragraphs of a TTML document, with their own `begin` and `end` (or `dur`) times.
+///
+/// SDH conventions are detected from paragraphs and spans colored other than white, and from
+/// sound descriptions written between square brackets.
+pub fn parse_document(content: &str) -> Result<TimedText, String> {
+  let mut document = TimedText::default();
+  let mut time_base = TimeBase {
+    frame_rate: 30.0,
+    tick_rate: 1.0,
+  };
+  let mut style_colors: HashMap<String, String> = HashMap::new();
+  let mut current_cue: Option<Cue> = None;
+
+  for event in EventReader::from_str(content) {
+    match event.map_err(|error| format!("Invalid TTML document: {}", error))? {
+      XmlEvent::StartElement {
+        name, attributes, ..
+      } => {
+        let attributes: HashMap<String, String> = attributes
+          .into_iter()
+          .map(|attribute| (attribute.name.local_name, attribute.value))
+          .collect();
+
+        if name.local_name == "p" || name.local_name == "span" {
+          // Inline color, or color of the referenced styles
+          let styles = attributes
+            .get("style")
+            .map(String::as_str)
+            .unwrap_or_default();
+          let mut colors = attributes.get("color").into_iter().chain(
+            styles
+              .split_whitespace()
+              .filter_map(|style| style_colors.get(style)),
+          );
+          if colors.any(|color| !is_white(color)) {
+            document.sdh = true;
+          }
+        }
+
+        match name.local_name.as_str() {
+          "tt" => {
+            document.lang = attributes.get("lang").cloned();
+            let frame_rate = attributes
+              .get("frameRate")
+              .and_then(|rate| rate.parse::<f64>().ok());
+            let sub_frame_rate = attributes
+              .get("subFrameRate")
+              .and_then(|rate| rate.parse::<f64>().ok())
+              .unwrap_or(1.0);
+            if let Some(frame_rate) = frame_rate {
+              time_base.frame_rate = frame_rate;
+            }
+            // Ticks are sub-frames when only the frame rate is set, seconds otherwise
+            time_base.tick_rate = attributes
+              .get("tickRate")
+              .and_then(|rate| rate.parse().ok())
+              .or_else(|| frame_rate.map(|frame_rate| frame_rate * sub_frame_rate))
+              .unwrap_or(1.0);
+          }
+          "style" => {
+            if let (Some(id), Some(color)) = (attributes.get("id"), attributes.get("color")) {
+              style_colors.insert(id.clone(), color.clone());
+            }
+          }
+          "p" => {
+            let begin = attributes
+              .get("begin")
+              .and_then(|begin| time_base.parse(begin));
+            let end = attributes
+              .get("end")
+              .and_then(|end| time_base.parse(end))
+              .or_else(|| {
+                let duration = attributes.get("dur").and_then(|dur| time_base.parse(dur))?;
+                Some(begin? + duration)
+              });
+
+            if let (Some(begin), Some(end)) = (begin, end) {
+              current_cue = Some(Cue {
+                begin,
+                end,
+                text: String::new(),
+              });
+            }
+          }
+          "br" => {
+            if let Some(ref mut cue) = current_cue {
+              cue.text.push('\n');
+            }
+          }
+          _ => {}
+        }
+      }
+      XmlEvent::Characters(text) | XmlEvent::Whitespace(text) => {
+        if let Some(ref mut cue) = current_cue {
+          cue.text.push_str(&text);
+        }
+      }
+      XmlEvent::EndElement { name } if name.local_name == "p" => {
+        if let Some(mut cue) = current_cue.take() {
+          cue.text = cue.text.trim().to_string();
+          if cue.text.contains('[') && cue.text.contains(']') {
+            document.sdh = true;
+          }
+          document.cues.push(cue);
+        }
+      }
+      _ => {}
+    }
+  }
+
+  Ok(document)
+}
+
+fn is_white(color: &str) -> bool {
+  matches!(
+    color.trim().to_lowercase().as_str(),
+    "white" | "#fff" | "#ffffff" | "#ffffffff" | "rgb(255,255,255)" | "transparent"
+  )
+}
+
+impl TimeBase {
+  /// Parse a time expression, as clock time (`00:01:02.500`, `00:01:02:12`) or as offset time
+  /// (`62.5s`, `1500ms`, `25f`, `100t`), in seconds
+  fn parse(&self, value: &str) -> Option<f64> {
+    let value = value.trim();
+
+    if value.contains(':') {
+      let parts: Vec<&str> = value.split(':').collect();
+      if parts.len() < 3 {
+        return None;
+      }
+      let hours: f64 = parts[0].parse().ok()?;
+      let minutes: f64 = parts[1].parse().ok()?;
+      let seconds: f64 = parts[2].parse().ok()?;
+      let frames: f64 = match parts.get(3) {
+        Some(frames) => frames.parse().ok()?,
+        None => 0.0,
+      };
+      return Some(hours * 3600.0 + minutes * 60.0 + seconds + frames / self.frame_rate);
+    }
+
+    let unit_position = value.find(|c: char| c.is_ascii_alphabetic())?;
+    let (count, unit) = value.split_at(unit_position);
+    let count: f64 = count.parse().ok()?;
+    match unit {
+      "h" => Some(count * 3600.0),
+      "m" => Some(count * 60.0),
+      "s" => Some(count),
+      "ms" => Some(count / 1000.0),
+      "f" => Some(count / self.frame_rate),
+      "t" => Some(count / self.tick_rate),
+      _ => None,
+    }
+  }
+}
+
+#[test]
+fn test_parse_document() {
+  let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tt xmlns="http://www.w3.org/ns/ttml" xmlns:tts="http://www.w3.org/ns/ttml#styling"
+    xmlns:ttp="http://www.w3.org/ns/ttml#parameter" xml:lang="fr" ttp:frameRate="25">
+  <head>
+    <styling>
+      <style xml:id="white" tts:color="white"/>
+      <style xml:id="yellow" tts:color="yellow"/>
+    </styling>
+  </head>
+  <body>
+    <div>
+      <p begin="00:00:01.500" end="00:00:03.000" style="white">Bonjour<br/>à tous</p>
+      <p begin="00:00:04:12" dur="2s">Au revoir</p>
+      <p begin="125.5s" end="127500ms">Fin</p>
+    </div>
+  </body>
+</tt>"#;
+
+  let document = parse_document(content).unwrap();
+  assert_eq!(document.lang, Some("fr".to_string()));
+  assert!(!document.sdh);
+  assert_eq!(
+    document.cues,
+    vec![
+      Cue {
+        begin: 1.5,
+        end: 3.0,
+        text: "Bonjour\nà tous".to_string()
+      },
+      Cue {
+        begin: 4.48,
+        end: 6.48,
+        text: "Au revoir".to_string()
+      },
+      Cue {
+        begin: 125.5,
+        end: 127.5,
+        text: "Fin".to_string()
+      },
+    ]
+  );
+
+  let content = r#"<tt xmlns="http://www.w3.org/ns/ttml" xmlns:tts="http://www.w3.org/ns/ttml#styling">
+  <body><div><p begin="1s" end="2s"><span tts:color="yellow">Bonjour</span></p></div></body>
+</tt>"#;
+  let document = parse_document(content).unwrap();
+  assert_eq!(document.lang, None);
+  assert!(document.sdh);
+
+  let content = r#"<tt xmlns="http://www.w3.org/ns/ttml" xmlns:tts="http://www.w3.org/ns/ttml#styling">
+  <head><styling><style xml:id="yellow" tts:color="yellow"/></styling></head>
+  <body><div><p begin="90t" end="120t" style="yellow">Bonjour</p></div></body>
+</tt>"#;
+  let document = parse_document(content).unwrap();
+  assert!(document.sdh);
+  assert_eq!(
+    (document.cues[0].begin, document.cues[0].end),
+    (90.0, 120.0)
+  );
+}
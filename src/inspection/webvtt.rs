@@ -0,0 +1,138 @@
+use std::{fs, path::Path};
+
+use super::{Cue, TimedText};
+
+pub fn read_document(path: &Path) -> Result<TimedText, String> {
+  let content = fs::read_to_string(path)
+    .map_err(|error| format!("Unable to read {}: {}", path.display(), error))?;
+  parse_document(&content)
+}
+
+/// Parse the cues of a WebVTT document, without their markup.
+///
+/// SDH conventions are detected from class spans (used for colors) and from sound descriptions
+/// written between square brackets.
+pub fn parse_document(content: &str) -> Result<TimedText, String> {
+  let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+  let mut blocks = content.split("\n\n").map(|block| block.trim_matches('\n'));
+
+  let header = blocks.next().unwrap_or_default();
+  if !header.starts_with("WEBVTT") {
+    return Err("Invalid WebVTT document: missing WEBVTT header".to_string());
+  }
+
+  let lang = header.lines().skip(1).find_map(|line| {
+    let mut parts = line.splitn(2, ':');
+    let name = parts.next()?.trim();
+    if name.eq_ignore_ascii_case("language") {
+      parts.next().map(|value| value.trim().to_string())
+    } else {
+      None
+    }
+  });
+
+  let mut document = TimedText {
+    lang,
+    ..Default::default()
+  };
+
+  for block in blocks {
+    let mut lines = block.lines();
+    let timing = match lines.find(|line| line.contains("-->")) {
+      Some(timing) => timing,
+      None => continue, // NOTE, STYLE and REGION blocks
+    };
+
+    let mut times = timing.splitn(2, "-->");
+    let begin = times.next().and_then(parse_timestamp);
+    let end = times
+      .next()
+      .and_then(|end| end.split_whitespace().next())
+      .and_then(parse_timestamp);
+
+    let (begin, end) = match (begin, end) {
+      (Some(begin), Some(end)) => (begin, end),
+      _ => return Err(format!("Invalid WebVTT cue timing: {}", timing)),
+    };
+
+    let markup = lines.collect::<Vec<_>>().join("\n");
+    if markup.contains("<c.") || (markup.contains('[') && markup.contains(']')) {
+      document.sdh = true;
+    }
+
+    document.cues.push(Cue {
+      begin,
+      end,
+      text: strip_markup(&markup),
+    });
+  }
+
+  Ok(document)
+}
+
+/// Parse a timestamp like `01:02.500` or `01:01:02.500`, in seconds
+fn parse_timestamp(value: &str) -> Option<f64> {
+  let parts: Vec<&str> = value.trim().split(':').collect();
+  let (hours, minutes, seconds) = match parts.as_slice() {
+    [minutes, seconds] => (0.0, minutes.parse::<f64>().ok()?, seconds),
+    [hours, minutes, seconds] => (
+      hours.parse::<f64>().ok()?,
+      minutes.parse::<f64>().ok()?,
+      seconds,
+    ),
+    _ => return None,
+  };
+  let seconds: f64 = seconds.parse().ok()?;
+  Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+fn strip_markup(markup: &str) -> String {
+  let mut text = String::new();
+  let mut in_tag = false;
+  for character in markup.chars() {
+    match character {
+      '<' => in_tag = true,
+      '>' if in_tag => in_tag = false,
+      _ if !in_tag => text.push(character),
+      _ => {}
+    }
+  }
+
+  text
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&nbsp;", " ")
+    .replace("&amp;", "&")
+    .trim()
+    .to_string()
+}
+
+#[test]
+fn test_parse_document() {
+  let content = "WEBVTT\nLanguage: fr\n\nNOTE exported from TTML\n\n1\n00:01.500 --> 00:03.000 align:center\nBonjour\n<i>à tous</i>\n\n01:00:04.000 --> 01:00:06.500\nFish &amp; chips\n";
+
+  let document = parse_document(content).unwrap();
+  assert_eq!(document.lang, Some("fr".to_string()));
+  assert!(!document.sdh);
+  assert_eq!(
+    document.cues,
+    vec![
+      Cue {
+        begin: 1.5,
+        end: 3.0,
+        text: "Bonjour\nà tous".to_string()
+      },
+      Cue {
+        begin: 3604.0,
+        end: 3606.5,
+        text: "Fish & chips".to_string()
+      },
+    ]
+  );
+
+  let content = "WEBVTT\n\n00:01.000 --> 00:02.000\n<c.yellow>[Musique]</c>\n";
+  let document = parse_document(content).unwrap();
+  assert_eq!(document.lang, None);
+  assert!(document.sdh);
+  assert_eq!(document.cues[0].text, "[Musique]");
+}
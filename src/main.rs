@@ -1,56 +1,104 @@
 #[macro_use]
 extern crate serde_derive;
 
-use crate::convert::convert_into_rdf;
-use clap::{App, Arg, ArgMatches};
 use mcai_worker_sdk::{
-  job::JobResult, start_worker, JsonSchema, McaiChannel, MessageEvent, Result, Version,
+  job::JobResult, start_worker, JsonSchema, McaiChannel, MessageError, MessageEvent, Result,
+  Version,
 };
 use std::env;
-use std::fs::File;
-use std::io::prelude::*;
+use std::process;
 
+mod checksum;
+mod command_line;
 mod convert;
+mod format_registry;
+mod graph_cache;
+mod graph_diff;
+mod graph_store;
+mod http;
+mod inspection;
 mod message;
 mod namespaces;
 mod perfect_memory;
+mod progression;
+mod publisher;
 mod rdf_graph;
 mod resource_model;
+mod retraction;
+mod sparql;
+mod stop_signal;
+mod storage_profile;
+mod transcript;
+mod validation;
 mod video_model;
+mod webhook;
 
 pub mod built_info {
   include!(concat!(env!("OUT_DIR"), "/built.rs"));
 }
 
 #[derive(Debug, Default)]
-struct RdfEvent {}
+struct RdfEvent {
+  stop_signal: stop_signal::StopSignal,
+}
 
-#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, Serialize)]
 pub enum Order {
   #[serde(rename = "publish_dash_and_ttml")]
   PublishDashAndTtml,
   #[serde(rename = "publish_metadata")]
+  #[default]
   PublishMetadata,
+  #[serde(rename = "publish_transcript")]
+  PublishTranscript,
+  #[serde(rename = "retract")]
+  Retract,
+  #[serde(rename = "update")]
+  Update,
 }
 
-impl Default for Order {
-  fn default() -> Self {
-    Order::PublishMetadata
-  }
-}
-
-#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema)]
 pub struct RdfWorkerParameters {
+  batch_parallelism: Option<usize>,
+  compute_sha256: Option<bool>,
+  dry_run: Option<bool>,
+  graph_cache_directory: Option<String>,
+  graph_store_endpoint: Option<String>,
+  graph_store_method: Option<graph_store::GraphStoreMethod>,
+  graph_store_password: Option<String>,
+  graph_store_username: Option<String>,
   input_paths: Option<Vec<String>>, // depends on the order?
+  merge_references: Option<bool>,
   ntriples: Option<bool>,
   order: Option<Order>,
-  perfect_memory_endpoint: String,
+  output_directory: Option<String>,
+  perfect_memory_delete_event_name: Option<String>,
+  perfect_memory_disrupt_on_stop: Option<bool>,
+  perfect_memory_endpoint: Option<String>,
   perfect_memory_event_name: Option<String>,
-  perfect_memory_username: String,
-  perfect_memory_password: String,
+  perfect_memory_username: Option<String>,
+  perfect_memory_password: Option<String>,
+  perfect_memory_max_poll_interval_secs: Option<u64>,
+  perfect_memory_poll_interval_secs: Option<u64>,
+  perfect_memory_retract_event_name: Option<String>,
+  perfect_memory_retry_publication: Option<bool>,
+  perfect_memory_timeout_secs: Option<u64>,
+  perfect_memory_wait_for_completion: Option<bool>,
+  publication_failure_policy: Option<publisher::FailurePolicy>,
+  publication_targets: Option<Vec<publisher::PublicationTarget>>,
+  #[serde(default)]
   reference: String,
-  storage: Option<String>,    // depends on the order?
+  references: Option<Vec<String>>,
+  resource_filter: Option<resource_model::ResourceFilter>,
+  retry_policy: Option<http::RetryPolicy>,
+  sparql_query_endpoint: Option<String>,
+  sparql_update_endpoint: Option<String>,
+  sparql_update_password: Option<String>,
+  sparql_update_username: Option<String>,
+  storage: Option<String>, // depends on the order?
+  transcript_sentences: Option<bool>,
   url_prefix: Option<String>, // depends on the order?
+  validate_graph: Option<bool>,
 }
 
 impl MessageEvent<RdfWorkerParameters> for RdfEvent {
@@ -79,54 +127,39 @@ In worker mode, the RDF metadata can be published to the Perfect Memory system.
     parameters: RdfWorkerParameters,
     job_result: JobResult,
   ) -> Result<JobResult> {
-    message::process(channel, parameters, job_result)
+    let _running_job = match self.stop_signal.running_job() {
+      Some(running_job) => running_job,
+      None => {
+        // The SDK requeues the jobs it does not implement, for another worker to process them
+        eprintln!(
+          "Stop requested, requeue job {} before exiting",
+          job_result.get_job_id()
+        );
+        return Err(MessageError::NotImplemented());
+      }
+    };
+    message::process(channel, parameters, job_result, &self.stop_signal)
   }
 }
 
-fn execute_command_line(matches: ArgMatches) {
-  let ntriples = true;
-  let reference = matches
-    .value_of("reference")
-    .expect("missing reference parameter");
-
-  let mut video_metadata =
-    message::get_video_metadata(&reference).expect("unable to get video metadata");
-  let si_video_files = message::get_files(&reference).expect("unable to get files for this video");
-  video_metadata.resources = resource_model::Resources {
-    items: si_video_files,
-  };
-
-  let rdf = convert_into_rdf(&video_metadata, ntriples).expect("unable to convert into rdf");
-
-  let extension = if ntriples { ".nt" } else { ".turtle" };
-
-  let mut file =
-    File::create(reference.to_string() + extension).expect("unable to open output file");
-  file
-    .write_all(&rdf.into_bytes())
-    .expect("error during writing RDF informations into the output file");
-
-  println!("RDF generated for reference {}{}", reference, extension);
-}
-
 fn main() {
   let message_event = RdfEvent::default();
 
-  if env::var("COMMAND_LINE").is_ok() {
-    let matches = App::new(message_event.get_name())
-      .version(message_event.get_version().to_string().as_str())
-      .author("Marc-Antoine Arnaud <maarnaud@media-io.com>")
-      .arg(
-        Arg::with_name("reference")
-          .short("r")
-          .help("video reference UUID")
-          .required(true)
-          .index(1),
-      )
-      .get_matches();
+  if let Err(error) = message_event.stop_signal.register_os_signals() {
+    eprintln!("Unable to listen to stop signals: {}", error);
+  }
+
+  let subcommand = env::args().nth(1).unwrap_or_default();
+  if env::var("COMMAND_LINE").is_ok() || command_line::SUBCOMMANDS.contains(&subcommand.as_str()) {
+    let version = message_event.get_version().to_string();
+    let matches = command_line::build_app(&message_event.get_name(), &version).get_matches();
 
-    execute_command_line(matches);
+    if let Err(error) = command_line::run(&matches, &message_event.stop_signal) {
+      eprintln!("{}", error);
+      process::exit(1);
+    }
   } else {
+    message_event.stop_signal.exit_when_stopped();
     start_worker(message_event);
   }
 }
@@ -1,176 +1,1317 @@
-use reqwest::StatusCode;
-use uuid::Uuid;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{
+  atomic::{AtomicUsize, Ordering},
+  Mutex,
+};
+use std::thread;
+
+use rdf::graph::Graph;
+use reqwest::{header::LINK, StatusCode};
 
-use crate::resource_model::{ExternalIds, Format, Resource, Resources};
+use crate::rdf_graph::merge_graph;
+use crate::resource_model::{Resource, ResourceFilter, Resources};
+use crate::retraction::Retraction;
+use crate::transcript::{group_sentences, Transcript};
+use crate::validation::validate_graph;
 use crate::video_model::metadata::Metadata;
 use crate::{Order, RdfWorkerParameters};
 use mcai_worker_sdk::{
   debug, error, info,
   job::{JobResult, JobStatus},
-  McaiChannel, MessageError, Result,
+  publish_job_progression, warn, McaiChannel, MessageError, Result,
+};
+
+use crate::checksum::{digest_file, FileDigest};
+use crate::convert::{convert_into_graph, new_graph, write_graph};
+use crate::format_registry::FormatRegistry;
+use crate::graph_cache::GraphCache;
+use crate::graph_diff::diff_graphs;
+use crate::graph_store::{GraphStoreConfig, GraphStorePublisher};
+use crate::http::{HttpClient, Idempotency};
+use crate::inspection::{get_local_path, inspect, read_timed_text, TimedText};
+use crate::perfect_memory::{PerfectMemoryPublisher, PmConfig};
+use crate::progression::*;
+use crate::publisher::{
+  write_output_file, Event, EventKind, FailurePolicy, FilePublisher, Publisher, TargetKind,
 };
+use crate::sparql::{fetch_video_graph, SparqlUpdateConfig, SparqlUpdatePublisher};
+use crate::stop_signal::StopSignal;
+use crate::storage_profile::{StorageProfile, StorageProfiles};
+use crate::webhook::{WebhookConfig, WebhookPublisher};
+
+// Number of references of a batch processed at the same time when not set
+const DEFAULT_BATCH_PARALLELISM: usize = 4;
+
+// Storage of the files delivered with the DASH and TTML order when not set
+const DEFAULT_DELIVERY_STORAGE: &str = "akamai-video-prod";
+// Long term storage of the files given with the metadata orders
+pub(crate) const LTS_STORAGE: &str = "ftv.video.media-io.com";
+
+/// Settings of a job, shared by all the references it processes
+struct Job {
+  targets: Vec<Target>,
+  report_targets: bool,
+  failure_policy: FailurePolicy,
+  http_client: HttpClient,
+  format_registry: FormatRegistry,
+  storage_profiles: StorageProfiles,
+  dry_run: bool,
+  output_directory: Option<String>,
+  pm_event_name: String,
+  pm_delete_event_name: String,
+  pm_retract_event_name: String,
+  url_prefix: Option<String>,
+  storage: Option<String>,
+  input_paths: Vec<String>,
+  with_sha256: bool,
+  transcript_sentences: bool,
+  validate_graph: bool,
+  order: Order,
+  resource_filter: ResourceFilter,
+  graph_cache: Option<GraphCache>,
+  sparql_query_endpoint: Option<String>,
+}
+
+/// Publisher of a job, with the serialization it expects
+struct Target {
+  name: String,
+  n_triples: bool,
+  publisher: Box<dyn Publisher>,
+}
+
+/// Events to publish for a reference, in order, with their graph
+struct Publications {
+  events: Vec<(EventKind, Graph)>,
+  /// Events of the publishers unable to delete some triples of a graph, when they differ
+  replacement_events: Option<Vec<(EventKind, Graph)>>,
+  /// Graph to cache once published
+  graph: Option<Graph>,
+}
 
-use crate::convert::convert_into_rdf;
-use crate::perfect_memory::{publish_to_perfect_memory, PmConfig};
-use reqwest::blocking::Client;
+impl Publications {
+  /// Events received by a publisher
+  fn get_events(&self, publisher: &dyn Publisher) -> &[(EventKind, Graph)] {
+    match self.replacement_events {
+      Some(ref replacement_events) if !publisher.publishes_diff() => replacement_events,
+      _ => &self.events,
+    }
+  }
+}
+
+/// Serialized graph of an event received by a target, reported instead of being published by a
+/// dry run
+#[derive(Debug, Serialize)]
+struct SerializedEvent {
+  target: String,
+  event: String,
+  triples: usize,
+  rdf: String,
+  #[serde(skip)]
+  n_triples: bool,
+}
+
+/// Outcome of the publication to a target
+#[derive(Debug, Serialize)]
+struct TargetReport {
+  target: String,
+  status: JobStatus,
+  message: Option<String>,
+}
+
+/// Outcome of the processing of a reference of a batch
+#[derive(Debug, Serialize)]
+struct ReferenceReport {
+  reference: String,
+  status: JobStatus,
+  message: Option<String>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  output_paths: Vec<String>,
+}
 
 pub fn process(
-  _channel: Option<McaiChannel>,
+  channel: Option<McaiChannel>,
   parameters: RdfWorkerParameters,
   job_result: JobResult,
+  stop_signal: &StopSignal,
 ) -> Result<JobResult> {
-  let config = PmConfig::from(parameters.clone());
+  let references = parameters.references.clone();
+  let merge_references = parameters.merge_references.unwrap_or(false);
+  let batch_parallelism = parameters
+    .batch_parallelism
+    .unwrap_or(DEFAULT_BATCH_PARALLELISM)
+    .max(1);
+  let reference = parameters.reference.clone();
+  let job = Job::new(parameters, stop_signal)?;
 
-  let n_triples = parameters.ntriples.unwrap_or(false);
-  let pm_event_name = parameters
-    .perfect_memory_event_name
-    .unwrap_or_else(|| "push_rdf_infos".to_string());
-  let reference = parameters.reference;
-  let url_prefix = parameters.url_prefix;
-  let storage = parameters.storage;
-  let input_paths = parameters.input_paths;
-  let order = parameters.order;
-
-  let rdf_triples = match order.unwrap_or_default() {
-    Order::PublishDashAndTtml => {
-      let input_paths = input_paths
-        .ok_or_else(|| MessageError::RuntimeError("Missing input_paths parameter".to_string()))?;
-
-      let storage = storage.unwrap_or_else(|| "akamai-video-prod".to_string());
-
-      let url_prefix = url_prefix
-        .unwrap_or_else(|| "http://videos-pmd.francetv.fr/innovation/SubTil/".to_string());
-
-      get_dash_and_ttml_rdf(
+  let references = match references {
+    Some(references) => references,
+    None if reference.is_empty() => {
+      return Err(MessageError::RuntimeError(
+        "Missing reference parameter".to_string(),
+      ))
+    }
+    None => return job.process_reference(&channel, &reference, job_result, stop_signal),
+  };
+  if !job.input_paths.is_empty() {
+    return Err(MessageError::RuntimeError(
+      "The input_paths parameter can not be used with the references parameter".to_string(),
+    ));
+  }
+
+  if merge_references {
+    job.process_merged_batch(
+      &channel,
+      &references,
+      batch_parallelism,
+      job_result,
+      stop_signal,
+    )
+  } else {
+    job.process_batch(
+      &channel,
+      &references,
+      batch_parallelism,
+      job_result,
+      stop_signal,
+    )
+  }
+}
+
+impl Job {
+  fn new(parameters: RdfWorkerParameters, stop_signal: &StopSignal) -> Result<Self> {
+    let http_client = HttpClient::new(parameters.retry_policy.clone().unwrap_or_default())
+      .map_err(MessageError::RuntimeError)?
+      .with_stop_signal(stop_signal);
+
+    Ok(Job {
+      targets: get_targets(&parameters, &http_client)?,
+      report_targets: parameters.publication_targets.is_some(),
+      failure_policy: parameters.publication_failure_policy.unwrap_or_default(),
+      http_client,
+      format_registry: FormatRegistry::from_env().map_err(MessageError::RuntimeError)?,
+      storage_profiles: StorageProfiles::from_env().map_err(MessageError::RuntimeError)?,
+      dry_run: parameters.dry_run.unwrap_or(false),
+      output_directory: parameters.output_directory,
+      pm_event_name: parameters
+        .perfect_memory_event_name
+        .unwrap_or_else(|| "push_rdf_infos".to_string()),
+      pm_delete_event_name: parameters
+        .perfect_memory_delete_event_name
+        .unwrap_or_else(|| "delete_rdf_triples".to_string()),
+      pm_retract_event_name: parameters
+        .perfect_memory_retract_event_name
+        .unwrap_or_else(|| "delete_rdf_infos".to_string()),
+      url_prefix: parameters.url_prefix,
+      storage: parameters.storage,
+      input_paths: parameters.input_paths.unwrap_or_default(),
+      with_sha256: parameters.compute_sha256.unwrap_or(false),
+      transcript_sentences: parameters.transcript_sentences.unwrap_or(false),
+      validate_graph: parameters.validate_graph.unwrap_or(false),
+      order: parameters.order.unwrap_or_default(),
+      resource_filter: parameters.resource_filter.unwrap_or_default(),
+      graph_cache: parameters
+        .graph_cache_directory
+        .as_deref()
+        .map(GraphCache::new),
+      sparql_query_endpoint: parameters.sparql_query_endpoint,
+    })
+  }
+
+  fn process_reference(
+    &self,
+    channel: &Option<McaiChannel>,
+    reference: &str,
+    job_result: JobResult,
+    stop_signal: &StopSignal,
+  ) -> Result<JobResult> {
+    let publications = self.get_publications(job_result.clone(), channel, reference)?;
+    let job_result = self.publish(
+      job_result,
+      channel,
+      Some(reference),
+      &publications,
+      stop_signal,
+    )?;
+    if stop_signal.is_raised() {
+      return Err(stopped(job_result));
+    }
+    if self.dry_run {
+      return Ok(job_result.with_status(JobStatus::Completed));
+    }
+
+    self.store_graphs(job_result.clone(), &[(reference, publications.graph)])?;
+    Ok(job_result.with_status(JobStatus::Completed))
+  }
+
+  /// Process and publish the references one by one, `parallelism` at a time
+  fn process_batch(
+    &self,
+    channel: &Option<McaiChannel>,
+    references: &[String],
+    parallelism: usize,
+    job_result: JobResult,
+    stop_signal: &StopSignal,
+  ) -> Result<JobResult> {
+    let job_id = job_result.get_job_id();
+    let results = run_batch(
+      channel,
+      job_id,
+      references,
+      parallelism,
+      stop_signal,
+      |reference| self.process_reference(&None, reference, JobResult::new(job_id), stop_signal),
+    );
+
+    let reports = results
+      .into_iter()
+      .map(|(reference, result)| ReferenceReport::new(reference, result))
+      .collect();
+    with_reports(job_result, reports)
+  }
+
+  /// Merge the graphs of the references, converted `parallelism` at a time, and publish them at once
+  fn process_merged_batch(
+    &self,
+    channel: &Option<McaiChannel>,
+    references: &[String],
+    parallelism: usize,
+    job_result: JobResult,
+    stop_signal: &StopSignal,
+  ) -> Result<JobResult> {
+    let job_id = job_result.get_job_id();
+    let results = run_batch(
+      channel,
+      job_id,
+      references,
+      parallelism,
+      stop_signal,
+      |reference| self.get_publications(JobResult::new(job_id), &None, reference),
+    );
+
+    let mut reports = vec![];
+    let mut merged_publications = Publications {
+      events: vec![],
+      replacement_events: None,
+      graph: None,
+    };
+    let mut graphs = vec![];
+    for (reference, result) in results {
+      let result = result.map(|publications| {
+        merge_events(&mut merged_publications.events, publications.events);
+        if let Some(replacement_events) = publications.replacement_events {
+          merge_events(
+            merged_publications
+              .replacement_events
+              .get_or_insert_with(Vec::new),
+            replacement_events,
+          );
+        }
+        graphs.push((reference.clone(), publications.graph));
+        JobResult::new(job_id)
+      });
+      reports.push(ReferenceReport::new(reference, result));
+    }
+
+    let job_result = self.publish(job_result, channel, None, &merged_publications, stop_signal)?;
+    if stop_signal.is_raised() {
+      return Err(stopped(job_result));
+    }
+    if !self.dry_run {
+      self.store_graphs(job_result.clone(), &graphs)?;
+    }
+    with_reports(job_result, reports)
+  }
+
+  /// Convert the RDF of a reference according to the order
+  fn get_publications(
+    &self,
+    job_result: JobResult,
+    channel: &Option<McaiChannel>,
+    reference: &str,
+  ) -> Result<Publications> {
+    let mut events = vec![];
+    let mut published_graph = None;
+
+    match self.order {
+      Order::PublishDashAndTtml => {
+        if self.input_paths.is_empty() {
+          return Err(MessageError::RuntimeError(
+            "Missing input_paths parameter".to_string(),
+          ));
+        }
+
+        let storage = self
+          .storage
+          .clone()
+          .unwrap_or_else(|| DEFAULT_DELIVERY_STORAGE.to_string());
+        let storage_profile = self
+          .storage_profiles
+          .resolve(&storage, self.url_prefix.clone());
+
+        let resources = get_input_resources(
+          &self.format_registry,
+          &self.input_paths,
+          reference,
+          &storage_profile,
+          &[],
+          self.with_sha256,
+        )
+        .map_err(|message| input_error(&job_result, &message))?;
+        let graph = get_resources_graph(job_result.clone(), channel, resources)?;
+        if self.validate_graph {
+          validate(job_result.clone(), channel, &graph)?;
+        }
+        events.push((EventKind::Insert, graph));
+      }
+      Order::PublishMetadata => {
+        let graph = self.get_lts_metadata_graph(job_result.clone(), channel, reference)?;
+        if self.validate_graph {
+          validate(job_result.clone(), channel, &graph)?;
+        }
+        let mut cached_graph = new_graph();
+        merge_graph(&mut cached_graph, &graph);
+        events.push((EventKind::Replace, graph));
+        published_graph = Some(cached_graph);
+      }
+      Order::PublishTranscript => {
+        if self.input_paths.is_empty() {
+          return Err(MessageError::RuntimeError(
+            "Missing input_paths parameter".to_string(),
+          ));
+        }
+
+        let texts = get_timed_texts(
+          job_result.clone(),
+          &self.format_registry,
+          &self.input_paths,
+          self.transcript_sentences,
+        )?;
+        let transcript = Transcript {
+          reference: reference.to_string(),
+          texts,
+        };
+
+        info!("Convert");
+        publish_job_progression(
+          channel.clone(),
+          job_result.get_job_id(),
+          PROGRESSION_CONVERT,
+        )?;
+        let graph = convert_into_graph(&transcript).map_err(|rdf_error| {
+          MessageError::ProcessingError(
+            job_result
+              .clone()
+              .with_status(JobStatus::Error)
+              .with_message(&rdf_error.to_string()),
+          )
+        })?;
+        if self.validate_graph {
+          validate(job_result.clone(), channel, &graph)?;
+        }
+        events.push((EventKind::Insert, graph));
+      }
+      Order::Retract => {
+        // The triples last published for the video are removed, the cache being emptied
+        let previous_graph = if self.graph_cache.is_some() || self.sparql_query_endpoint.is_some() {
+          get_previous_graph(
+            job_result.clone(),
+            &self.http_client,
+            reference,
+            &self.graph_cache,
+            &self.sparql_query_endpoint,
+          )?
+        } else {
+          new_graph()
+        };
+        let graph = if previous_graph.is_empty() {
+          get_retract_graph(job_result, channel, reference)?
+        } else {
+          previous_graph
+        };
+        events.push((EventKind::Retract, graph));
+        published_graph = Some(new_graph());
+      }
+      Order::Update => {
+        let previous_graph = get_previous_graph(
+          job_result.clone(),
+          &self.http_client,
+          reference,
+          &self.graph_cache,
+          &self.sparql_query_endpoint,
+        )?;
+
+        let graph = self.get_lts_metadata_graph(job_result.clone(), channel, reference)?;
+        if self.validate_graph {
+          validate(job_result.clone(), channel, &graph)?;
+        }
+        return Ok(self.get_update_publications(&previous_graph, graph));
+      }
+    };
+
+    Ok(Publications {
+      events,
+      replacement_events: None,
+      graph: published_graph,
+    })
+  }
+
+  /// Events updating the published graph of a reference, from its previous graph
+  fn get_update_publications(&self, previous_graph: &Graph, graph: Graph) -> Publications {
+    let diff = diff_graphs(previous_graph, &graph);
+    info!(
+      "Graph update: {} triples to delete, {} triples to insert",
+      diff.deleted.count(),
+      diff.inserted.count()
+    );
+
+    let mut events = vec![];
+    let mut replacement_events = None;
+    let replaced = self
+      .targets
+      .iter()
+      .any(|target| !target.publisher.publishes_diff());
+    if replaced {
+      let mut replacement = vec![];
+      if !diff.deleted.is_empty() || !diff.inserted.is_empty() {
+        let mut replacement_graph = new_graph();
+        merge_graph(&mut replacement_graph, &graph);
+        replacement.push((EventKind::Replace, replacement_graph));
+      }
+      replacement_events = Some(replacement);
+    }
+
+    if !diff.deleted.is_empty() {
+      events.push((EventKind::Delete, diff.deleted));
+    }
+    if !diff.inserted.is_empty() {
+      events.push((EventKind::Insert, diff.inserted));
+    }
+
+    Publications {
+      events,
+      replacement_events,
+      graph: Some(graph),
+    }
+  }
+
+  /// Graph of the video metadata, with the input files stored on the long term storage
+  fn get_lts_metadata_graph(
+    &self,
+    job_result: JobResult,
+    channel: &Option<McaiChannel>,
+    reference: &str,
+  ) -> Result<Graph> {
+    let storage_profile = self.storage_profiles.resolve(LTS_STORAGE, None);
+    let resources = get_input_resources(
+      &self.format_registry,
+      &self.input_paths,
+      reference,
+      &storage_profile,
+      &["lts".to_string()],
+      self.with_sha256,
+    )
+    .map_err(|message| input_error(&job_result, &message))?;
+    get_metadata_graph(
+      job_result,
+      channel,
+      &self.http_client,
+      reference,
+      &self.resource_filter,
+      resources,
+    )
+  }
+
+  /// Publish the events of a reference, or of a batch of references when not set, to each target
+  fn publish(
+    &self,
+    job_result: JobResult,
+    channel: &Option<McaiChannel>,
+    reference: Option<&str>,
+    publications: &Publications,
+    stop_signal: &StopSignal,
+  ) -> Result<JobResult> {
+    let mut job_result = job_result;
+    if self.dry_run || self.output_directory.is_some() {
+      let serialized_events = self.serialize_events(job_result.clone(), publications)?;
+      job_result =
+        self.write_output(job_result, reference.unwrap_or("batch"), &serialized_events)?;
+      if self.dry_run {
+        return report_dry_run(job_result, serialized_events);
+      }
+    }
+
+    let replacement_events = publications.replacement_events.as_ref();
+    if publications.events.is_empty() && replacement_events.iter().all(|events| events.is_empty()) {
+      info!("Nothing to publish");
+      return Ok(job_result.with_message("No change to publish"));
+    }
+
+    // The progression moves forward over the events of all the targets
+    let event_count = self
+      .targets
+      .iter()
+      .map(|target| publications.get_events(target.publisher.as_ref()).len())
+      .sum();
+    let mut first_event = 0;
+
+    let mut reports = vec![];
+    for target in &self.targets {
+      if stop_signal.is_raised() {
+        break;
+      }
+
+      let events: Vec<_> = publications
+        .get_events(target.publisher.as_ref())
+        .iter()
+        .map(|(kind, graph)| {
+          let progression = ProgressionRange::publication(first_event, event_count);
+          first_event += 1;
+          (*kind, graph, progression)
+        })
+        .collect();
+      let result = self.publish_to_target(
         job_result.clone(),
-        input_paths,
-        &reference,
-        &storage,
-        &url_prefix,
-        n_triples,
-      )?
+        channel,
+        reference,
+        target,
+        &events,
+        stop_signal,
+      );
+      if !self.report_targets {
+        return result;
+      }
+
+      reports.push(TargetReport::new(&target.name, &result));
+      match result {
+        Ok(target_job_result) => job_result = target_job_result,
+        Err(_) if self.failure_policy == FailurePolicy::FailFast => break,
+        Err(_) => {}
+      }
     }
-    Order::PublishMetadata => {
-      let input_paths = input_paths.unwrap_or_default();
-      get_metadata_rdf(job_result.clone(), input_paths, &reference, n_triples)?
+
+    if !self.report_targets {
+      return Ok(job_result);
     }
-  };
+    with_target_reports(job_result, reports, self.targets.len())
+  }
 
-  info!("Publish to PerfectMemory");
-  debug!("rdf_triples:\n{}", rdf_triples);
+  /// Publish the events to a target, serialized as it expects, each with its part of the job
+  /// progression
+  fn publish_to_target(
+    &self,
+    job_result: JobResult,
+    channel: &Option<McaiChannel>,
+    reference: Option<&str>,
+    target: &Target,
+    events: &[(EventKind, &Graph, ProgressionRange)],
+    stop_signal: &StopSignal,
+  ) -> Result<JobResult> {
+    let mut job_result = job_result;
+    for (kind, graph, progression) in events {
+      let name = self.get_event_name(*kind);
+      let rdf = write_rdf(job_result.clone(), graph, target.n_triples)?;
+      info!("Publish {} to {}", name, target.name);
+      debug!("rdf_triples:\n{}", rdf);
+      publish_job_progression(channel.clone(), job_result.get_job_id(), progression.start)?;
 
-  publish_to_perfect_memory(
-    job_result.clone(),
-    &config.client_id,
-    &pm_event_name,
-    &config.api_key,
-    &config.endpoint,
-    &rdf_triples,
-  )?;
+      let event = Event {
+        reference,
+        kind: *kind,
+        name,
+        graph,
+        rdf: &rdf,
+        n_triples: target.n_triples,
+        progression: *progression,
+      };
+      job_result = target
+        .publisher
+        .publish(job_result, channel, &event, stop_signal)?;
 
-  Ok(job_result.with_status(JobStatus::Completed))
+      if stop_signal.is_raised() {
+        break;
+      }
+    }
+
+    Ok(job_result)
+  }
+
+  /// Perfect Memory event name of an event, also naming its output file
+  fn get_event_name(&self, kind: EventKind) -> &str {
+    match kind {
+      EventKind::Insert | EventKind::Replace => &self.pm_event_name,
+      EventKind::Delete => &self.pm_delete_event_name,
+      EventKind::Retract => &self.pm_retract_event_name,
+    }
+  }
+
+  /// Serialize the events received by each target
+  fn serialize_events(
+    &self,
+    job_result: JobResult,
+    publications: &Publications,
+  ) -> Result<Vec<SerializedEvent>> {
+    let mut serialized_events = vec![];
+    for target in &self.targets {
+      for (kind, graph) in publications.get_events(target.publisher.as_ref()) {
+        serialized_events.push(SerializedEvent {
+          target: target.name.clone(),
+          event: self.get_event_name(*kind).to_string(),
+          triples: graph.count(),
+          rdf: write_rdf(job_result.clone(), graph, target.n_triples)?,
+          n_triples: target.n_triples,
+        });
+      }
+    }
+    Ok(serialized_events)
+  }
+
+  /// Write the RDF of each event in the output directory, and add its path to the job result
+  fn write_output(
+    &self,
+    job_result: JobResult,
+    name: &str,
+    serialized_events: &[SerializedEvent],
+  ) -> Result<JobResult> {
+    let directory = match self.output_directory {
+      Some(ref directory) => Path::new(directory),
+      None => return Ok(job_result),
+    };
+
+    let mut output_paths = vec![];
+    for serialized_event in serialized_events {
+      // The files of several targets are told apart by the target name
+      let name = if self.targets.len() > 1 {
+        format!("{}_{}", name, serialized_event.target)
+      } else {
+        name.to_string()
+      };
+      let path = write_output_file(
+        directory,
+        job_result.get_job_id(),
+        &name,
+        &serialized_event.event,
+        &serialized_event.rdf,
+        serialized_event.n_triples,
+      )
+      .map_err(|error| {
+        MessageError::ProcessingError(
+          job_result
+            .clone()
+            .with_status(JobStatus::Error)
+            .with_message(&error),
+        )
+      })?;
+      output_paths.push(path);
+    }
+
+    Ok(job_result.with_destination_paths(&mut output_paths))
+  }
+
+  fn store_graphs<R: AsRef<str>>(
+    &self,
+    job_result: JobResult,
+    graphs: &[(R, Option<Graph>)],
+  ) -> Result<()> {
+    let graph_cache = match self.graph_cache {
+      Some(ref graph_cache) => graph_cache,
+      None => return Ok(()),
+    };
+
+    for (reference, graph) in graphs {
+      if let Some(graph) = graph {
+        graph_cache
+          .store(reference.as_ref(), graph)
+          .map_err(|error| {
+            MessageError::ProcessingError(
+              job_result
+                .clone()
+                .with_status(JobStatus::Error)
+                .with_message(&error),
+            )
+          })?;
+      }
+    }
+    Ok(())
+  }
 }
 
-fn get_dash_and_ttml_rdf(
+/// Run `process` on each reference from `parallelism` threads, publishing the job progression as
+/// references are done. References left when a stop is requested are not processed.
+fn run_batch<T, F>(
+  channel: &Option<McaiChannel>,
+  job_id: u64,
+  references: &[String],
+  parallelism: usize,
+  stop_signal: &StopSignal,
+  process: F,
+) -> Vec<(String, Result<T>)>
+where
+  T: Send,
+  F: Fn(&str) -> Result<T> + Sync,
+{
+  let next_index = AtomicUsize::new(0);
+  let results = Mutex::new(vec![]);
+
+  thread::scope(|scope| {
+    for _ in 0..parallelism.min(references.len()) {
+      scope.spawn(|| loop {
+        let index = next_index.fetch_add(1, Ordering::SeqCst);
+        let reference = match references.get(index) {
+          Some(reference) if !stop_signal.is_raised() => reference,
+          _ => break,
+        };
+
+        info!("Process reference {}", reference);
+        let result = process(reference);
+
+        let mut results = results.lock().unwrap();
+        results.push((index, reference.clone(), result));
+        let progression = results.len() * 100 / references.len();
+        if let Err(error) = publish_job_progression(channel.clone(), job_id, progression as u8) {
+          warn!("Unable to publish job progression: {:?}", error);
+        }
+      });
+    }
+  });
+
+  // References left when a stop was requested fail, so that the job does not complete
+  let mut results = results.into_inner().unwrap();
+  let processed: BTreeSet<usize> = results.iter().map(|(index, _, _)| *index).collect();
+  for (index, reference) in references.iter().enumerate() {
+    if !processed.contains(&index) {
+      let error = MessageError::RuntimeError("Not processed, stop requested".to_string());
+      results.push((index, reference.clone(), Err(error)));
+    }
+  }
+  results.sort_by_key(|(index, _, _)| *index);
+  results
+    .into_iter()
+    .map(|(_, reference, result)| (reference, result))
+    .collect()
+}
+
+impl ReferenceReport {
+  fn new(reference: String, result: Result<JobResult>) -> Self {
+    match result {
+      Ok(job_result) => ReferenceReport {
+        reference,
+        status: JobStatus::Completed,
+        message: get_message(&job_result),
+        output_paths: job_result.get_destination_paths().clone(),
+      },
+      Err(MessageError::ProcessingError(job_result)) => ReferenceReport {
+        reference,
+        status: JobStatus::Error,
+        message: get_message(&job_result),
+        output_paths: job_result.get_destination_paths().clone(),
+      },
+      Err(error) => ReferenceReport {
+        reference,
+        status: JobStatus::Error,
+        message: Some(format!("{:?}", error)),
+        output_paths: vec![],
+      },
+    }
+  }
+}
+
+/// Job result with the graph and triple count of each event, without publishing them
+fn report_dry_run(
   job_result: JobResult,
-  input_paths: Vec<String>,
-  reference: &str,
-  storage: &str,
-  url_prefix: &str,
-  n_triples: bool,
-) -> Result<String> {
-  let mut references = vec![];
+  serialized_events: Vec<SerializedEvent>,
+) -> Result<JobResult> {
+  let triples: usize = serialized_events
+    .iter()
+    .map(|serialized_event| serialized_event.triples)
+    .sum();
+  let message = format!(
+    "Dry run: {} triples of {} events not published",
+    triples,
+    serialized_events.len()
+  );
+  info!("{}", message);
+
+  job_result
+    .with_message(&message)
+    .with_json("publications", &serialized_events)
+    .map_err(MessageError::RuntimeError)
+}
 
-  let mut ttml_paths: Vec<Resource> = input_paths
+/// Add the graphs of the events to the graphs of the merged events of the same kind
+fn merge_events(merged_events: &mut Vec<(EventKind, Graph)>, events: Vec<(EventKind, Graph)>) {
+  for (event_kind, graph) in events {
+    match merged_events
+      .iter_mut()
+      .find(|(kind, _)| *kind == event_kind)
+    {
+      Some((_, merged_graph)) => merge_graph(merged_graph, &graph),
+      None => {
+        let mut merged_graph = new_graph();
+        merge_graph(&mut merged_graph, &graph);
+        merged_events.push((event_kind, merged_graph));
+      }
+    }
+  }
+}
+
+/// Publication targets of the job.
+///
+/// Without the `publication_targets` parameter, the job publishes to the Graph Store Protocol or
+/// SPARQL Update endpoint when set, to Perfect Memory otherwise.
+fn get_targets(parameters: &RdfWorkerParameters, http_client: &HttpClient) -> Result<Vec<Target>> {
+  let n_triples = parameters.ntriples.unwrap_or(false);
+
+  let publication_targets = match parameters.publication_targets {
+    Some(ref publication_targets) => publication_targets,
+    None => {
+      let kind = get_default_target_kind(parameters)?;
+      check_merged_target(parameters, &kind)?;
+      return Ok(vec![Target {
+        name: kind.get_type().to_string(),
+        n_triples,
+        publisher: get_publisher(&kind, parameters, http_client),
+      }]);
+    }
+  };
+
+  if parameters.graph_store_endpoint.is_some() || parameters.sparql_update_endpoint.is_some() {
+    return Err(MessageError::RuntimeError(
+      "The publication_targets parameter can not be used with graph_store_endpoint or sparql_update_endpoint".to_string(),
+    ));
+  }
+  if publication_targets.is_empty() {
+    return Err(MessageError::RuntimeError(
+      "Empty publication_targets parameter".to_string(),
+    ));
+  }
+
+  publication_targets
     .iter()
-    .filter(|path| path.ends_with(".ttml"))
-    .map(|path| Resource {
-      id: Uuid::new_v4().to_urn().to_string(),
-      created_via: "Media-IO".to_string(),
-      format: Format {
-        id: "caption-ttml".to_string(),
-        label: "caption/ttml".to_string(),
-        kind: "caption".to_string(),
-        mime_type: "urn:mimetype:application/xml+ttml".to_string(),
+    .map(|target| {
+      check_merged_target(parameters, &target.kind)?;
+      Ok(Target {
+        name: target
+          .name
+          .clone()
+          .unwrap_or_else(|| target.kind.get_type().to_string()),
+        n_triples: target.ntriples.unwrap_or(n_triples),
+        publisher: get_publisher(&target.kind, parameters, http_client),
+      })
+    })
+    .collect()
+}
+
+/// Graph Store Protocol and SPARQL Update targets publish the graph of each video on its own, and
+/// can not receive the merged graph of a batch
+fn check_merged_target(parameters: &RdfWorkerParameters, kind: &TargetKind) -> Result<()> {
+  match kind {
+    TargetKind::GraphStore { .. } | TargetKind::SparqlUpdate { .. }
+      if parameters.merge_references.unwrap_or(false) =>
+    {
+      Err(MessageError::RuntimeError(format!(
+        "The merge_references parameter can not be used with a {} target",
+        kind.get_type()
+      )))
+    }
+    _ => Ok(()),
+  }
+}
+
+/// Graph Store Protocol or SPARQL Update target when its endpoint is set, Perfect Memory target
+/// otherwise, whose credentials are only required when publishing
+fn get_default_target_kind(parameters: &RdfWorkerParameters) -> Result<TargetKind> {
+  match (
+    &parameters.graph_store_endpoint,
+    &parameters.sparql_update_endpoint,
+  ) {
+    (Some(_), Some(_)) => Err(MessageError::RuntimeError(
+      "Only one of graph_store_endpoint and sparql_update_endpoint can be set".to_string(),
+    )),
+    (Some(endpoint), None) => Ok(TargetKind::GraphStore {
+      endpoint: endpoint.clone(),
+      username: parameters.graph_store_username.clone(),
+      password: parameters.graph_store_password.clone(),
+      method: parameters.graph_store_method,
+    }),
+    (None, Some(endpoint)) => Ok(TargetKind::SparqlUpdate {
+      endpoint: endpoint.clone(),
+      username: parameters.sparql_update_username.clone(),
+      password: parameters.sparql_update_password.clone(),
+    }),
+    (None, None) => {
+      let dry_run = parameters.dry_run.unwrap_or(false);
+      let required = |value: &Option<String>, name: &str| match value {
+        Some(value) => Ok(value.clone()),
+        None if dry_run => Ok(String::default()),
+        None => Err(MessageError::RuntimeError(format!(
+          "Missing {} parameter",
+          name
+        ))),
+      };
+      Ok(TargetKind::PerfectMemory {
+        endpoint: required(
+          &parameters.perfect_memory_endpoint,
+          "perfect_memory_endpoint",
+        )?,
+        username: required(
+          &parameters.perfect_memory_username,
+          "perfect_memory_username",
+        )?,
+        password: required(
+          &parameters.perfect_memory_password,
+          "perfect_memory_password",
+        )?,
+      })
+    }
+  }
+}
+
+fn get_publisher(
+  kind: &TargetKind,
+  parameters: &RdfWorkerParameters,
+  http_client: &HttpClient,
+) -> Box<dyn Publisher> {
+  let http_client = http_client.clone();
+
+  match kind {
+    TargetKind::PerfectMemory {
+      endpoint,
+      username,
+      password,
+    } => Box::new(PerfectMemoryPublisher {
+      http_client,
+      config: PmConfig {
+        endpoint: endpoint.clone(),
+        client_id: username.clone(),
+        api_key: password.clone(),
+        ..PmConfig::from(parameters.clone())
       },
-      storage: storage.to_string(),
-      path: None,
-      filename: Some(path.to_string()),
-      audio_tracks: vec![],
-      text_tracks: vec![],
-      video_tracks: vec![],
-      created_at: None,
-      updated_at: None,
-      ratio: None,
-      width: None,
-      height: None,
-      index: None,
-      copyright: None,
-      filesize_bytes: None,
-      bitrate_kbps: None,
-      md5_checksum: None,
-      tags: vec![],
-      url: Some(format!("{}{}", url_prefix, path)),
-      version: None,
-      lang: None,
-      external_ids: ExternalIds {
-        video_id: Some(reference.to_string()),
-        legacy_id: None,
-        group_id: None,
-        job_id: None,
-        remote_id: None,
+    }),
+    TargetKind::GraphStore {
+      endpoint,
+      username,
+      password,
+      method,
+    } => Box::new(GraphStorePublisher {
+      http_client,
+      config: GraphStoreConfig {
+        endpoint: endpoint.clone(),
+        username: username.clone(),
+        password: password.clone(),
+        method: method.unwrap_or_default(),
       },
-    })
+    }),
+    TargetKind::SparqlUpdate {
+      endpoint,
+      username,
+      password,
+    } => Box::new(SparqlUpdatePublisher {
+      http_client,
+      config: SparqlUpdateConfig {
+        endpoint: endpoint.clone(),
+        username: username.clone(),
+        password: password.clone(),
+      },
+    }),
+    TargetKind::File { directory } => Box::new(FilePublisher {
+      directory: PathBuf::from(directory),
+    }),
+    TargetKind::Webhook {
+      url,
+      headers,
+      body_template,
+      content_type,
+      expected_statuses,
+    } => Box::new(WebhookPublisher {
+      http_client,
+      config: WebhookConfig {
+        url: url.clone(),
+        headers: headers.clone(),
+        body_template: body_template.clone(),
+        content_type: content_type.clone(),
+        expected_statuses: expected_statuses.clone(),
+      },
+      poll_config: PmConfig::from(parameters.clone()),
+    }),
+  }
+}
+
+impl TargetReport {
+  fn new(target: &str, result: &Result<JobResult>) -> Self {
+    let (status, message) = match result {
+      Ok(job_result) => (JobStatus::Completed, get_message(job_result)),
+      Err(MessageError::ProcessingError(job_result)) => (JobStatus::Error, get_message(job_result)),
+      Err(error) => (JobStatus::Error, Some(format!("{:?}", error))),
+    };
+    TargetReport {
+      target: target.to_string(),
+      status,
+      message,
+    }
+  }
+}
+
+/// Job result with the report of each publication target, in error if any target failed
+fn with_target_reports(
+  job_result: JobResult,
+  reports: Vec<TargetReport>,
+  targets: usize,
+) -> Result<JobResult> {
+  let failures = reports
+    .iter()
+    .filter(|report| report.status == JobStatus::Error)
+    .count();
+  let job_result = job_result
+    .with_json("targets", &reports)
+    .map_err(MessageError::RuntimeError)?;
+
+  if failures > 0 {
+    return Err(MessageError::ProcessingError(
+      job_result
+        .with_status(JobStatus::Error)
+        .with_message(&format!(
+          "{} of {} publication targets failed",
+          failures, targets
+        )),
+    ));
+  }
+  Ok(job_result)
+}
+
+/// Job result with the report of each reference, in error if any reference failed
+fn with_reports(job_result: JobResult, reports: Vec<ReferenceReport>) -> Result<JobResult> {
+  let failures = reports
+    .iter()
+    .filter(|report| report.status == JobStatus::Error)
+    .count();
+  let mut output_paths = reports
+    .iter()
+    .flat_map(|report| report.output_paths.clone())
     .collect();
+  let job_result = job_result
+    .with_destination_paths(&mut output_paths)
+    .with_json("references", &reports)
+    .map_err(MessageError::RuntimeError)?;
+
+  if failures > 0 {
+    return Err(MessageError::ProcessingError(
+      job_result
+        .with_status(JobStatus::Error)
+        .with_message(&format!(
+          "{} of {} references failed",
+          failures,
+          reports.len()
+        )),
+    ));
+  }
+  Ok(job_result.with_status(JobStatus::Completed))
+}
+
+/// Error of a job stopped before the end of its publication, the published graph being partial
+fn stopped(job_result: JobResult) -> MessageError {
+  MessageError::ProcessingError(
+    job_result
+      .with_status(JobStatus::Error)
+      .with_message("Stopped: the job has been stopped before the end of its publication"),
+  )
+}
 
-  let mut dash_manifest_paths: Vec<Resource> = input_paths
+/// Message set in a job result by `JobResult::with_message`
+pub(crate) fn get_message(job_result: &JobResult) -> Option<String> {
+  job_result
+    .get_parameters()
     .iter()
-    .filter(|path| path.ends_with(".mpd"))
-    .map(|path| Resource {
-      id: Uuid::new_v4().to_urn().to_string(),
-      created_via: "Media-IO".to_string(),
-      format: Format {
-        id: "playlist-dash".to_string(),
-        label: "playlist/dash".to_string(),
-        kind: "playlist".to_string(),
-        mime_type: "urn:mimetype:application/dash+xml".to_string(),
-      },
-      storage: storage.to_string(),
-      path: None,
-      filename: Some(path.to_string()),
-      audio_tracks: vec![],
-      text_tracks: vec![],
-      video_tracks: vec![],
-      created_at: None,
-      updated_at: None,
-      ratio: None,
-      width: None,
-      height: None,
-      index: None,
-      copyright: None,
-      filesize_bytes: None,
-      bitrate_kbps: None,
-      md5_checksum: None,
-      tags: vec![],
-      url: Some(format!("{}{}", url_prefix, path)),
-      version: None,
-      lang: None,
-      external_ids: ExternalIds {
-        video_id: Some(reference.to_string()),
-        legacy_id: None,
-        group_id: None,
-        job_id: None,
-        remote_id: None,
-      },
+    .find(|parameter| parameter.id == "message")
+    .and_then(|parameter| parameter.value.as_ref())
+    .and_then(|value| value.as_str())
+    .map(String::from)
+}
+
+fn get_previous_graph(
+  job_result: JobResult,
+  http_client: &HttpClient,
+  reference: &str,
+  graph_cache: &Option<GraphCache>,
+  sparql_query_endpoint: &Option<String>,
+) -> Result<Graph> {
+  let previous_graph = if let Some(sparql_query_endpoint) = sparql_query_endpoint {
+    info!("Get previous graph from {}", sparql_query_endpoint);
+    fetch_video_graph(http_client, sparql_query_endpoint, reference)
+  } else if let Some(graph_cache) = graph_cache {
+    info!("Get previous graph from cache");
+    graph_cache
+      .load(reference)
+      .map(|graph| graph.unwrap_or_else(|| Graph::new(None)))
+  } else {
+    return Err(MessageError::RuntimeError(
+      "Missing graph_cache_directory or sparql_query_endpoint parameter".to_string(),
+    ));
+  };
+
+  previous_graph.map_err(|error| {
+    MessageError::ProcessingError(
+      job_result
+        .with_status(JobStatus::Error)
+        .with_message(&error),
+    )
+  })
+}
+
+/// Check the graph against the shapes of the published classes
+fn validate(job_result: JobResult, channel: &Option<McaiChannel>, graph: &Graph) -> Result<()> {
+  info!("Validate");
+  publish_job_progression(
+    channel.clone(),
+    job_result.get_job_id(),
+    PROGRESSION_VALIDATE,
+  )?;
+
+  let violations = validate_graph(graph);
+  if violations.is_empty() {
+    return Ok(());
+  }
+
+  for violation in &violations {
+    warn!("{}: {}", violation.focus_node, violation.message);
+  }
+  Err(MessageError::ProcessingError(
+    job_result
+      .with_status(JobStatus::Error)
+      .with_message(&format!(
+        "Invalid graph: {} constraint violations",
+        violations.len()
+      )),
+  ))
+}
+
+fn write_rdf(job_result: JobResult, graph: &Graph, n_triples: bool) -> Result<String> {
+  write_graph(graph, n_triples).map_err(|rdf_error| {
+    MessageError::ProcessingError(
+      job_result
+        .with_status(JobStatus::Error)
+        .with_message(&rdf_error.to_string()),
+    )
+  })
+}
+
+/// Resources of the files given as input paths, tagged with their format tags and `tags`.
+///
+/// Files readable on the local filesystem get their size and checksums, and are inspected to
+/// describe their content. Fails on a file name matching no registered format.
+pub(crate) fn get_input_resources(
+  format_registry: &FormatRegistry,
+  input_paths: &[String],
+  reference: &str,
+  storage_profile: &StorageProfile,
+  tags: &[String],
+  with_sha256: bool,
+) -> std::result::Result<Vec<Resource>, String> {
+  input_paths
+    .iter()
+    .map(|path| {
+      let entry = format_registry
+        .find(path)
+        .ok_or_else(|| format!("No format registered for {}", path))?;
+      let tags = tags.iter().chain(entry.tags.iter()).cloned().collect();
+      let builder = Resource::builder(path, entry.format.clone())
+        .storage_profile(storage_profile)
+        .reference(reference)
+        .tags(tags);
+
+      let local_path = match get_local_path(path) {
+        Some(local_path) => local_path,
+        None => return Ok(builder.build()),
+      };
+
+      let builder = match get_local_digest(&local_path, with_sha256) {
+        Some(digest) => builder.digest(digest),
+        None => builder,
+      };
+      Ok(
+        inspect(
+          builder,
+          &entry.format,
+          &local_path,
+          &storage_profile.storage,
+        )
+        .build(),
+      )
     })
-    .collect();
+    .collect()
+}
+
+fn input_error(job_result: &JobResult, message: &str) -> MessageError {
+  MessageError::ProcessingError(
+    job_result
+      .clone()
+      .with_status(JobStatus::Error)
+      .with_message(message),
+  )
+}
+
+fn get_local_digest(path: &Path, with_sha256: bool) -> Option<FileDigest> {
+  info!("Compute checksums of {}", path.display());
+  digest_file(path, with_sha256)
+    .map_err(|error| {
+      warn!(
+        "Unable to compute checksums of {}: {}",
+        path.display(),
+        error
+      )
+    })
+    .ok()
+}
+
+/// Cues of the subtitle files given as input paths, grouped by sentence if `sentences` is set
+fn get_timed_texts(
+  job_result: JobResult,
+  format_registry: &FormatRegistry,
+  input_paths: &[String],
+  sentences: bool,
+) -> Result<Vec<TimedText>> {
+  let to_error = |message: String| {
+    MessageError::ProcessingError(
+      job_result
+        .clone()
+        .with_status(JobStatus::Error)
+        .with_message(&message),
+    )
+  };
+
+  input_paths
+    .iter()
+    .map(|path| {
+      let entry = format_registry
+        .find(path)
+        .ok_or_else(|| to_error(format!("No format registered for {}", path)))?;
+      let local_path = get_local_path(path)
+        .ok_or_else(|| to_error(format!("Unable to read {}: not a local file", path)))?;
 
-  references.append(&mut ttml_paths);
-  references.append(&mut dash_manifest_paths);
+      info!("Read subtitles of {}", path);
+      let mut text = read_timed_text(&entry.format, &local_path).map_err(to_error)?;
+      if sentences {
+        text.cues = group_sentences(text.cues);
+      }
+      Ok(text)
+    })
+    .collect()
+}
 
-  let resources = Resources { items: references };
+fn get_resources_graph(
+  job_result: JobResult,
+  channel: &Option<McaiChannel>,
+  resources: Vec<Resource>,
+) -> Result<Graph> {
+  let resources = Resources { items: resources };
 
-  convert_into_rdf(&resources, n_triples).map_err(|rdf_error| {
+  info!("Convert");
+  publish_job_progression(
+    channel.clone(),
+    job_result.get_job_id(),
+    PROGRESSION_CONVERT,
+  )?;
+  convert_into_graph(&resources).map_err(|rdf_error| {
     MessageError::ProcessingError(
       job_result
         .clone()
@@ -180,14 +1321,45 @@ fn get_dash_and_ttml_rdf(
   })
 }
 
-fn get_metadata_rdf(
+fn get_retract_graph(
   job_result: JobResult,
-  input_paths: Vec<String>,
+  channel: &Option<McaiChannel>,
   reference: &str,
-  n_triples: bool,
-) -> Result<String> {
+) -> Result<Graph> {
+  let retraction = Retraction {
+    reference: reference.to_string(),
+  };
+
+  info!("Convert");
+  publish_job_progression(
+    channel.clone(),
+    job_result.get_job_id(),
+    PROGRESSION_CONVERT,
+  )?;
+  convert_into_graph(&retraction).map_err(|rdf_error| {
+    MessageError::ProcessingError(
+      job_result
+        .with_status(JobStatus::Error)
+        .with_message(&rdf_error.to_string()),
+    )
+  })
+}
+
+fn get_metadata_graph(
+  job_result: JobResult,
+  channel: &Option<McaiChannel>,
+  http_client: &HttpClient,
+  reference: &str,
+  resource_filter: &ResourceFilter,
+  input_resources: Vec<Resource>,
+) -> Result<Graph> {
   info!("Get video metadata");
-  let mut video_metadata = get_video_metadata(reference).map_err(|error| {
+  publish_job_progression(
+    channel.clone(),
+    job_result.get_job_id(),
+    PROGRESSION_FETCH_METADATA,
+  )?;
+  let mut video_metadata = get_video_metadata(http_client, reference).map_err(|error| {
     MessageError::ProcessingError(
       job_result
         .clone()
@@ -196,8 +1368,13 @@ fn get_metadata_rdf(
     )
   })?;
   info!("Get files");
+  publish_job_progression(
+    channel.clone(),
+    job_result.get_job_id(),
+    PROGRESSION_FETCH_FILES,
+  )?;
 
-  let mut si_video_files = get_files(reference).map_err(|error| {
+  let mut si_video_files = get_files(http_client, reference, resource_filter).map_err(|error| {
     MessageError::ProcessingError(
       job_result
         .clone()
@@ -206,73 +1383,18 @@ fn get_metadata_rdf(
     )
   })?;
 
-  for path in input_paths {
-    let format = if path.ends_with(".ttml") {
-      Format {
-        id: "caption-ttml".to_string(),
-        label: "caption/ttml".to_string(),
-        kind: "caption".to_string(),
-        mime_type: "urn:mimetype:application/xml+ttml".to_string(),
-      }
-    } else {
-      Format {
-        id: "video-mp4".to_string(),
-        label: "video/mp4".to_string(),
-        kind: "video".to_string(),
-        mime_type: "urn:mimetype:video/mp4".to_string(),
-      }
-    };
-
-    let url_prefix = "https://ftv.video.media-io.com/";
-
-    let mut tags = vec!["lts".to_string()];
-
-    if path.ends_with("-qaa.mp4") {
-      tags.push("qaa".to_string())
-    }
-    if path.ends_with("-qad.mp4") {
-      tags.push("qad".to_string())
-    }
-
-    si_video_files.push(Resource {
-      id: Uuid::new_v4().to_urn().to_string(),
-      created_via: "Media-IO".to_string(),
-      format,
-      storage: "ftv.video.media-io.com".to_string(),
-      path: None,
-      filename: Some(path.to_owned()),
-      audio_tracks: vec![],
-      text_tracks: vec![],
-      video_tracks: vec![],
-      created_at: None,
-      updated_at: None,
-      ratio: None,
-      width: None,
-      height: None,
-      index: None,
-      copyright: None,
-      filesize_bytes: None,
-      bitrate_kbps: None,
-      md5_checksum: None,
-      tags,
-      url: Some(format!("{}{}", url_prefix, path)),
-      version: None,
-      lang: None,
-      external_ids: ExternalIds {
-        video_id: Some(reference.to_string()),
-        legacy_id: None,
-        group_id: None,
-        job_id: None,
-        remote_id: None,
-      },
-    });
-  }
+  si_video_files.extend(input_resources);
 
   video_metadata.resources = Resources {
     items: si_video_files,
   };
   info!("Convert");
-  convert_into_rdf(&video_metadata, n_triples).map_err(|rdf_error| {
+  publish_job_progression(
+    channel.clone(),
+    job_result.get_job_id(),
+    PROGRESSION_CONVERT,
+  )?;
+  convert_into_graph(&video_metadata).map_err(|rdf_error| {
     MessageError::ProcessingError(
       job_result
         .clone()
@@ -282,14 +1404,13 @@ fn get_metadata_rdf(
   })
 }
 
-pub fn get_video_metadata(reference: &str) -> std::result::Result<Metadata, String> {
+pub fn get_video_metadata(
+  http_client: &HttpClient,
+  reference: &str,
+) -> std::result::Result<Metadata, String> {
   let url = "https://gatewayvf.webservices.francetelevisions.fr/v1/videos/".to_owned() + reference;
 
-  let client = Client::builder()
-    .build()
-    .map_err(|error| format!("Unable to create HTTP client: {}", error))?;
-
-  let response = client.get(url.as_str()).send().map_err(|e| e.to_string())?;
+  let response = http_client.send(http_client.get(url.as_str()), Idempotency::Idempotent)?;
 
   let status = response.status();
 
@@ -301,23 +1422,601 @@ pub fn get_video_metadata(reference: &str) -> std::result::Result<Metadata, Stri
   response.json().map_err(|e| e.to_string())
 }
 
-pub fn get_files(reference: &str) -> std::result::Result<Vec<Resource>, String> {
-  let url = "https://gatewayvf.webservices.francetelevisions.fr/v1/files?external_ids.video_id="
-    .to_owned()
-    + reference;
+const FILES_PAGE_LIMIT: usize = 100;
+// Bound of the listing of files, in case the gateway ignores the page parameter
+const FILES_MAX_PAGES: usize = 100;
 
-  let client = Client::builder()
-    .build()
-    .map_err(|error| format!("Unable to create HTTP client: {}", error))?;
+pub fn get_files(
+  http_client: &HttpClient,
+  reference: &str,
+  resource_filter: &ResourceFilter,
+) -> std::result::Result<Vec<Resource>, String> {
+  list_files(
+    http_client,
+    "https://gatewayvf.webservices.francetelevisions.fr/v1/files",
+    reference,
+    resource_filter,
+  )
+}
 
-  let response = client.get(url.as_str()).send().map_err(|e| e.to_string())?;
+fn list_files(
+  http_client: &HttpClient,
+  url: &str,
+  reference: &str,
+  resource_filter: &ResourceFilter,
+) -> std::result::Result<Vec<Resource>, String> {
+  let mut files = vec![];
+  let mut page = 1;
+  let mut next_url: Option<String> = None;
+  let mut seen_urls = BTreeSet::new();
+  let mut seen_ids = BTreeSet::new();
 
-  let status = response.status();
+  for _ in 0..FILES_MAX_PAGES {
+    let request = if let Some(ref next_url) = next_url {
+      http_client.get(next_url.as_str())
+    } else {
+      http_client
+        .get(url)
+        .query(&[("external_ids.video_id", reference)])
+        .query(&resource_filter.to_query())
+        .query(&[("page", page), ("limit", FILES_PAGE_LIMIT)])
+    };
 
-  if status != StatusCode::OK {
-    error!("{:?}", response);
-    return Err(format!("Bad response status: {:?}", status));
+    let response = http_client.send(request, Idempotency::Idempotent)?;
+
+    let status = response.status();
+
+    if status != StatusCode::OK {
+      error!("{:?}", response);
+      return Err(format!("Bad response status: {:?}", status));
+    }
+
+    let link_next = response
+      .headers()
+      .get(LINK)
+      .and_then(|link| link.to_str().ok())
+      .and_then(get_next_link)
+      // The target can be relative to the requested URL
+      .and_then(|link| response.url().join(&link).ok())
+      .map(|url| url.to_string());
+
+    let items: Vec<Resource> = response.json().map_err(|e| e.to_string())?;
+    let count = items.len();
+    // Gateways ignoring the page parameter return the same files on each page
+    let mut new_items: Vec<Resource> = items
+      .into_iter()
+      .filter(|item| seen_ids.insert(item.id.clone()))
+      .collect();
+    let added = new_items.len();
+    files.append(&mut new_items);
+
+    if count == 0 {
+      return Ok(files);
+    }
+
+    if let Some(link_next) = link_next {
+      if !seen_urls.insert(link_next.clone()) {
+        return Err(format!("Files pagination loops on {}", link_next));
+      }
+      next_url = Some(link_next);
+    } else if next_url.is_none() && count == FILES_PAGE_LIMIT && added > 0 {
+      page += 1;
+    } else {
+      return Ok(files);
+    }
   }
 
-  response.json().map_err(|e| e.to_string())
+  Err(format!(
+    "More than {} pages of files for {}",
+    FILES_MAX_PAGES, reference
+  ))
+}
+
+/// Extract the `rel="next"` target of a `Link` header (RFC 8288)
+fn get_next_link(link: &str) -> Option<String> {
+  link.split(',').find_map(|link_value| {
+    let mut parts = link_value.split(';');
+    let target = parts.next()?.trim();
+    let is_next = parts.any(|param| {
+      let param = param.trim().replace(' ', "");
+      param == "rel=\"next\"" || param == "rel=next"
+    });
+
+    if is_next && target.starts_with('<') && target.ends_with('>') {
+      Some(target[1..target.len() - 1].to_string())
+    } else {
+      None
+    }
+  })
+}
+
+#[test]
+fn test_list_files_pagination_loop() {
+  use crate::http::{start_test_server_with_responses, RetryPolicy};
+
+  let files: serde_json::Value =
+    serde_json::from_str(&std::fs::read_to_string("tests/files.json").unwrap()).unwrap();
+  let response = format!(
+    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nLink: </files?page=2>; rel=\"next\"\r\n\r\n[{}]",
+    files[0]
+  );
+  let (address, server) = start_test_server_with_responses(vec![response; 2]);
+
+  let http_client = HttpClient::new(RetryPolicy::default()).unwrap();
+  let result = list_files(
+    &http_client,
+    &format!("{}/files", address),
+    "99787afd-ba2d-410f-b03e-66cf2efb3ed5",
+    &ResourceFilter::default(),
+  );
+  assert_eq!(
+    result.unwrap_err(),
+    format!("Files pagination loops on {}/files?page=2", address)
+  );
+
+  let requests = server.join().unwrap();
+  assert!(requests[0].starts_with("GET /files?external_ids.video_id="));
+  assert!(requests[1].starts_with("GET /files?page=2 HTTP/1.1\r\n"));
+}
+
+#[test]
+fn test_list_files_page_ignored() {
+  use crate::http::{start_test_server_with_responses, RetryPolicy};
+
+  let files: serde_json::Value =
+    serde_json::from_str(&std::fs::read_to_string("tests/files.json").unwrap()).unwrap();
+  let page: Vec<serde_json::Value> = (0..FILES_PAGE_LIMIT)
+    .map(|index| {
+      let mut file = files[0].clone();
+      file["id"] = format!("file-{}", index).into();
+      file
+    })
+    .collect();
+  let response = format!(
+    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{}",
+    serde_json::Value::from(page)
+  );
+  let (address, server) = start_test_server_with_responses(vec![response; 2]);
+
+  let http_client = HttpClient::new(RetryPolicy::default()).unwrap();
+  let files = list_files(
+    &http_client,
+    &format!("{}/files", address),
+    "99787afd-ba2d-410f-b03e-66cf2efb3ed5",
+    &ResourceFilter::default(),
+  )
+  .unwrap();
+  assert_eq!(files.len(), FILES_PAGE_LIMIT);
+
+  let requests = server.join().unwrap();
+  assert_eq!(requests.len(), 2);
+  assert!(requests[1].contains("page=2"));
+}
+
+#[test]
+fn test_get_next_link() {
+  let link = r#"<https://gateway/v1/files?page=1>; rel="prev", <https://gateway/v1/files?page=3>; rel="next""#;
+  assert_eq!(
+    get_next_link(link),
+    Some("https://gateway/v1/files?page=3".to_string())
+  );
+
+  let link = r#"<https://gateway/v1/files?page=1>; rel="first""#;
+  assert_eq!(get_next_link(link), None);
+}
+
+#[test]
+fn test_resource_filter_query() {
+  let filter = ResourceFilter {
+    format_type: Some("video".to_string()),
+    tags: vec!["lts".to_string(), "qad".to_string()],
+    storage: None,
+  };
+
+  assert_eq!(
+    filter.to_query(),
+    vec![
+      ("format.type", "video".to_string()),
+      ("tags", "lts".to_string()),
+      ("tags", "qad".to_string()),
+    ]
+  );
+}
+
+#[test]
+fn test_run_batch_reports() {
+  let references: Vec<String> = (0..10).map(|index| format!("ref-{}", index)).collect();
+  let results = run_batch(
+    &None,
+    0,
+    &references,
+    3,
+    &StopSignal::default(),
+    |reference| {
+      if reference == "ref-4" {
+        Err(MessageError::RuntimeError("Not found".to_string()))
+      } else {
+        Ok(JobResult::new(0).with_message(reference))
+      }
+    },
+  );
+  assert_eq!(
+    results
+      .iter()
+      .map(|(reference, _)| reference.clone())
+      .collect::<Vec<_>>(),
+    references
+  );
+
+  let reports = results
+    .into_iter()
+    .map(|(reference, result)| ReferenceReport::new(reference, result))
+    .collect();
+  match with_reports(JobResult::new(0), reports) {
+    Err(MessageError::ProcessingError(job_result)) => {
+      assert_eq!(
+        get_message(&job_result),
+        Some("1 of 10 references failed".to_string())
+      );
+    }
+    result => panic!("unexpected result {:?}", result),
+  }
+}
+
+#[test]
+fn test_run_batch_stopped() {
+  let references: Vec<String> = (0..5).map(|index| format!("ref-{}", index)).collect();
+  let stop_signal = StopSignal::default();
+  let results = run_batch(&None, 0, &references, 1, &stop_signal, |reference| {
+    if reference == "ref-1" {
+      stop_signal.raise();
+    }
+    Ok(JobResult::new(0))
+  });
+  assert_eq!(
+    results
+      .iter()
+      .map(|(reference, result)| (reference.as_str(), result.is_ok()))
+      .collect::<Vec<_>>(),
+    vec![
+      ("ref-0", true),
+      ("ref-1", true),
+      ("ref-2", false),
+      ("ref-3", false),
+      ("ref-4", false)
+    ]
+  );
+
+  let reports = results
+    .into_iter()
+    .map(|(reference, result)| ReferenceReport::new(reference, result))
+    .collect();
+  assert!(with_reports(JobResult::new(0), reports).is_err());
+}
+
+#[test]
+fn test_process_dry_run() {
+  let parameters = RdfWorkerParameters {
+    dry_run: Some(true),
+    input_paths: Some(vec!["/delivery/99787afd/manifest.mpd".to_string()]),
+    ntriples: Some(true),
+    order: Some(Order::PublishDashAndTtml),
+    reference: "99787afd-ba2d-410f-b03e-66cf2efb3ed5".to_string(),
+    ..Default::default()
+  };
+  let job_result = process(None, parameters, JobResult::new(0), &StopSignal::default()).unwrap();
+  let message = get_message(&job_result).unwrap();
+  assert!(message.starts_with("Dry run: "), "{}", message);
+  assert!(message.ends_with(" triples of 1 events not published"));
+
+  let publications = job_result
+    .get_parameters()
+    .iter()
+    .find(|parameter| parameter.id == "publications")
+    .and_then(|parameter| parameter.value.as_ref())
+    .and_then(|value| value.as_str())
+    .unwrap();
+  let publications: serde_json::Value = serde_json::from_str(publications).unwrap();
+  assert_eq!(publications[0]["event"], "push_rdf_infos");
+  assert!(publications[0]["rdf"]
+    .as_str()
+    .unwrap()
+    .contains("manifest.mpd"));
+}
+
+#[test]
+fn test_process_output_directory() {
+  use std::fs;
+
+  let directory = std::env::temp_dir().join(format!("rdf_worker_{}", uuid::Uuid::new_v4()));
+  let parameters = RdfWorkerParameters {
+    dry_run: Some(true),
+    input_paths: Some(vec!["/delivery/99787afd/manifest.mpd".to_string()]),
+    order: Some(Order::PublishDashAndTtml),
+    output_directory: Some(directory.to_string_lossy().to_string()),
+    reference: "99787afd-ba2d-410f-b03e-66cf2efb3ed5".to_string(),
+    ..Default::default()
+  };
+  let job_result = process(None, parameters, JobResult::new(42), &StopSignal::default()).unwrap();
+
+  let path = directory.join("42_99787afd-ba2d-410f-b03e-66cf2efb3ed5_push_rdf_infos.ttl");
+  assert_eq!(
+    job_result.get_destination_paths(),
+    &vec![path.to_string_lossy().to_string()]
+  );
+  assert!(fs::read_to_string(&path).unwrap().contains("manifest.mpd"));
+  fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_input_resources_mime_type() {
+  let storage_profile = StorageProfiles::default().resolve(DEFAULT_DELIVERY_STORAGE, None);
+  let resources = get_input_resources(
+    &FormatRegistry::default(),
+    &["/delivery/99787afd/FRA.ttml".to_string()],
+    "99787afd-ba2d-410f-b03e-66cf2efb3ed5",
+    &storage_profile,
+    &[],
+    false,
+  )
+  .unwrap();
+  let graph = convert_into_graph(&Resources { items: resources }).unwrap();
+  let rdf = write_graph(&graph, true).unwrap();
+
+  assert!(
+    rdf.contains("\"urn:mimetype:application/xml+ttml\""),
+    "{}",
+    rdf
+  );
+  assert!(!rdf.contains("urn:mimetype:urn:mimetype:"));
+}
+
+#[test]
+fn test_input_resources_unknown_format() {
+  let storage_profile = StorageProfiles::default().resolve(DEFAULT_DELIVERY_STORAGE, None);
+  let error = get_input_resources(
+    &FormatRegistry::default(),
+    &[
+      "/delivery/99787afd/manifest.mpd".to_string(),
+      "/delivery/99787afd/archive.zip".to_string(),
+    ],
+    "99787afd-ba2d-410f-b03e-66cf2efb3ed5",
+    &storage_profile,
+    &[],
+    false,
+  )
+  .unwrap_err();
+  assert_eq!(
+    error,
+    "No format registered for /delivery/99787afd/archive.zip"
+  );
+}
+
+#[test]
+fn test_process_publication_targets() {
+  use crate::http::start_test_server;
+
+  let directory = std::env::temp_dir().join(format!("rdf_worker_{}", uuid::Uuid::new_v4()));
+  let (webhook_address, webhook_server) = start_test_server(1, 200);
+  let (sparql_address, sparql_server) = start_test_server(1, 400);
+  let publication_targets = serde_json::json!([
+    {"type": "file", "name": "archive", "ntriples": true, "directory": directory},
+    {"type": "sparql_update", "endpoint": format!("{}/update", sparql_address)},
+    {"type": "webhook", "url": format!("{}/hook", webhook_address), "headers": {"X-Token": "secret"}}
+  ]);
+
+  let parameters = RdfWorkerParameters {
+    input_paths: Some(vec!["/delivery/99787afd/manifest.mpd".to_string()]),
+    order: Some(Order::PublishDashAndTtml),
+    publication_failure_policy: Some(FailurePolicy::BestEffort),
+    publication_targets: Some(serde_json::from_value(publication_targets).unwrap()),
+    reference: "99787afd-ba2d-410f-b03e-66cf2efb3ed5".to_string(),
+    ..Default::default()
+  };
+  let job_result = match process(None, parameters, JobResult::new(7), &StopSignal::default()) {
+    Err(MessageError::ProcessingError(job_result)) => job_result,
+    result => panic!("unexpected result {:?}", result),
+  };
+  assert_eq!(
+    get_message(&job_result),
+    Some("1 of 3 publication targets failed".to_string())
+  );
+
+  let targets = job_result
+    .get_parameters()
+    .iter()
+    .find(|parameter| parameter.id == "targets")
+    .and_then(|parameter| parameter.value.as_ref())
+    .and_then(|value| value.as_str())
+    .unwrap();
+  let targets: serde_json::Value = serde_json::from_str(targets).unwrap();
+  assert_eq!(targets[0]["target"], "archive");
+  assert_eq!(targets[0]["status"], "completed");
+  assert_eq!(targets[1]["target"], "sparql_update");
+  assert_eq!(targets[1]["status"], "error");
+  assert_eq!(targets[2]["status"], "completed");
+
+  let path = directory.join("7_99787afd-ba2d-410f-b03e-66cf2efb3ed5_push_rdf_infos.nt");
+  assert_eq!(
+    job_result.get_destination_paths(),
+    &vec![path.to_string_lossy().to_string()]
+  );
+  std::fs::remove_dir_all(directory).unwrap();
+
+  sparql_server.join().unwrap();
+  let request = &webhook_server.join().unwrap()[0];
+  assert!(request.starts_with("POST /hook HTTP/1.1\r\n"));
+  assert!(request.contains("x-token: secret\r\n"));
+  assert!(request.contains(r#""job_id":7,"reference":"99787afd-ba2d-410f-b03e-66cf2efb3ed5","event":"push_rdf_infos","format":"turtle""#));
+}
+
+#[test]
+fn test_retract_publications() {
+  use crate::convert::read_n_triples;
+
+  let directory = std::env::temp_dir().join(format!("rdf_worker_{}", uuid::Uuid::new_v4()));
+  let graph_cache_directory = directory.to_string_lossy().to_string();
+  let published = r#"<http://resources.idfrancetv.fr/medias/99787afd> <http://purl.org/dc/elements/1.1/title> "Title" .
+<http://resources.idfrancetv.fr/medias/99787afd> <http://purl.org/dc/elements/1.1/description> "Description" .
+"#;
+  GraphCache::new(&graph_cache_directory)
+    .store("99787afd", &read_n_triples(published).unwrap())
+    .unwrap();
+
+  let parameters = RdfWorkerParameters {
+    dry_run: Some(true),
+    graph_cache_directory: Some(graph_cache_directory),
+    order: Some(Order::Retract),
+    ..Default::default()
+  };
+  let job = Job::new(parameters, &StopSignal::default()).unwrap();
+
+  // The published triples are retracted, and the cache emptied once they are
+  let publications = job
+    .get_publications(JobResult::new(0), &None, "99787afd")
+    .unwrap();
+  assert_eq!(publications.events.len(), 1);
+  assert_eq!(publications.events[0].0, EventKind::Retract);
+  assert_eq!(publications.events[0].1.count(), 2);
+  assert!(publications.graph.unwrap().is_empty());
+
+  // A video never published is retracted by its node
+  let publications = job
+    .get_publications(JobResult::new(0), &None, "e4b5c3d2")
+    .unwrap();
+  assert_eq!(publications.events[0].1.count(), 1);
+  std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_update_publications() {
+  use crate::convert::read_n_triples;
+  use crate::http::start_test_server_with_responses;
+
+  let previous_graph = read_n_triples(
+    r#"<http://resources.idfrancetv.fr/medias/99787afd> <http://purl.org/dc/elements/1.1/title> "Title" .
+<http://resources.idfrancetv.fr/medias/99787afd> <http://purl.org/dc/elements/1.1/description> "Description" .
+"#,
+  )
+  .unwrap();
+  let rdf = r#"<http://resources.idfrancetv.fr/medias/99787afd> <http://purl.org/dc/elements/1.1/title> "New title" .
+<http://resources.idfrancetv.fr/medias/99787afd> <http://purl.org/dc/elements/1.1/description> "Description" .
+"#;
+
+  // Perfect Memory receives the deleted then the inserted triples only
+  let (address, server) = start_test_server_with_responses(vec![
+    "HTTP/1.1 201 Created\r\nLocation: /v1/requests/1\r\nContent-Length: 0\r\n".to_string();
+    2
+  ]);
+  let parameters = RdfWorkerParameters {
+    perfect_memory_endpoint: Some(address),
+    perfect_memory_username: Some("client".to_string()),
+    perfect_memory_password: Some("key".to_string()),
+    perfect_memory_wait_for_completion: Some(false),
+    ..Default::default()
+  };
+  let job = Job::new(parameters, &StopSignal::default()).unwrap();
+  let publications = job.get_update_publications(&previous_graph, read_n_triples(rdf).unwrap());
+  job
+    .publish(
+      JobResult::new(0),
+      &None,
+      Some("99787afd"),
+      &publications,
+      &StopSignal::default(),
+    )
+    .unwrap();
+
+  let published: Vec<(String, String)> = server
+    .join()
+    .unwrap()
+    .iter()
+    .map(|request| {
+      let body = &request[request.find("\r\n{").unwrap() + 2..];
+      let body: serde_json::Value = serde_json::from_str(body).unwrap();
+      let rdf = base64::decode(body["inputs"]["infos_graph"]["value"].as_str().unwrap()).unwrap();
+      (
+        body["name"].as_str().unwrap().to_string(),
+        String::from_utf8(rdf).unwrap(),
+      )
+    })
+    .collect();
+  assert_eq!(published.len(), 2);
+  assert_eq!(published[0].0, "delete_rdf_triples");
+  assert!(published[0].1.contains("\"Title\""));
+  assert!(!published[0].1.contains("Description"));
+  assert_eq!(published[1].0, "push_rdf_infos");
+  assert!(published[1].1.contains("\"New title\""));
+  assert!(!published[1].1.contains("Description"));
+
+  // A dry run serializes the events received by each target
+  let publication_targets = serde_json::json!([
+    {"type": "sparql_update", "endpoint": "http://localhost/update"},
+    {"type": "file", "name": "archive", "directory": "/tmp"}
+  ]);
+  let parameters = RdfWorkerParameters {
+    publication_targets: Some(serde_json::from_value(publication_targets).unwrap()),
+    ..Default::default()
+  };
+  let job = Job::new(parameters, &StopSignal::default()).unwrap();
+  let publications = job.get_update_publications(&previous_graph, read_n_triples(rdf).unwrap());
+  let serialized_events = job
+    .serialize_events(JobResult::new(0), &publications)
+    .unwrap();
+  let serialized: Vec<(&str, &str, usize)> = serialized_events
+    .iter()
+    .map(|event| (event.target.as_str(), event.event.as_str(), event.triples))
+    .collect();
+  assert_eq!(
+    serialized,
+    vec![
+      ("sparql_update", "push_rdf_infos", 2),
+      ("archive", "delete_rdf_triples", 1),
+      ("archive", "push_rdf_infos", 1)
+    ]
+  );
+}
+
+#[test]
+fn test_merged_batch_targets() {
+  let parameters = RdfWorkerParameters {
+    merge_references: Some(true),
+    graph_store_endpoint: Some("http://localhost:3030/ds/data".to_string()),
+    ..Default::default()
+  };
+  assert!(Job::new(parameters, &StopSignal::default()).is_err());
+
+  let publication_targets = serde_json::json!([
+    {"type": "sparql_update", "endpoint": "http://localhost:3030/ds/update"}
+  ]);
+  let parameters = RdfWorkerParameters {
+    merge_references: Some(true),
+    publication_targets: Some(serde_json::from_value(publication_targets).unwrap()),
+    ..Default::default()
+  };
+  assert!(Job::new(parameters, &StopSignal::default()).is_err());
+}
+
+#[test]
+fn test_perfect_memory_credentials() {
+  // Required by the default Perfect Memory target only
+  let error = Job::new(RdfWorkerParameters::default(), &StopSignal::default())
+    .err()
+    .unwrap();
+  assert!(matches!(
+    error,
+    MessageError::RuntimeError(message) if message == "Missing perfect_memory_endpoint parameter"
+  ));
+
+  let parameters = RdfWorkerParameters {
+    graph_store_endpoint: Some("http://localhost:3030/ds/data".to_string()),
+    ..Default::default()
+  };
+  assert!(Job::new(parameters, &StopSignal::default()).is_ok());
+
+  let parameters = RdfWorkerParameters {
+    dry_run: Some(true),
+    ..Default::default()
+  };
+  assert!(Job::new(parameters, &StopSignal::default()).is_ok());
 }
@@ -1,12 +1,21 @@
-use std::{thread, time};
+use std::{
+  collections::BTreeMap,
+  thread,
+  time::{Duration, Instant},
+};
 
 use mcai_worker_sdk::{
-  error,
+  error, info,
   job::{JobResult, JobStatus},
-  MessageError, Result,
+  publish_job_progression, McaiChannel, MessageError, Parameter, ParameterValue, Result,
 };
 use reqwest::header::{CACHE_CONTROL, CONTENT_TYPE, LOCATION};
+use serde_json::Value;
 
+use crate::http::{HttpClient, Idempotency};
+use crate::progression::ProgressionRange;
+use crate::publisher::{Event, Publisher};
+use crate::stop_signal::StopSignal;
 use crate::RdfWorkerParameters;
 
 #[derive(Serialize)]
@@ -26,18 +35,44 @@ pub(crate) struct PmConfig {
   pub(crate) endpoint: String,
   pub(crate) client_id: String,
   pub(crate) api_key: String,
+  pub(crate) retry_publication: bool,
+  pub(crate) wait_for_completion: bool,
+  pub(crate) timeout: Duration,
+  pub(crate) poll_interval: Duration,
+  pub(crate) max_poll_interval: Duration,
+  /// Ask Perfect Memory to disrupt the running request when the job is stopped
+  pub(crate) disrupt_on_stop: bool,
 }
 
 impl From<RdfWorkerParameters> for PmConfig {
   fn from(parameters: RdfWorkerParameters) -> Self {
     PmConfig {
-      endpoint: parameters.perfect_memory_endpoint.clone(),
-      client_id: parameters.perfect_memory_username.clone(),
-      api_key: parameters.perfect_memory_password,
+      endpoint: parameters.perfect_memory_endpoint.unwrap_or_default(),
+      client_id: parameters.perfect_memory_username.unwrap_or_default(),
+      api_key: parameters.perfect_memory_password.unwrap_or_default(),
+      retry_publication: parameters.perfect_memory_retry_publication.unwrap_or(false),
+      wait_for_completion: parameters
+        .perfect_memory_wait_for_completion
+        .unwrap_or(true),
+      timeout: Duration::from_secs(parameters.perfect_memory_timeout_secs.unwrap_or(3600)),
+      poll_interval: Duration::from_secs(
+        parameters.perfect_memory_poll_interval_secs.unwrap_or(10),
+      ),
+      max_poll_interval: Duration::from_secs(
+        parameters
+          .perfect_memory_max_poll_interval_secs
+          .unwrap_or(60),
+      ),
+      disrupt_on_stop: parameters.perfect_memory_disrupt_on_stop.unwrap_or(false),
     }
   }
 }
 
+#[derive(Serialize)]
+struct PmStatusUpdate {
+  status: u32,
+}
+
 #[derive(Serialize)]
 struct PmRequestBody {
   client_id: String,
@@ -45,55 +80,145 @@ struct PmRequestBody {
   inputs: Inputs,
 }
 
-#[derive(Debug, Deserialize)]
-struct PmResponseBody {
-  id: String,
-  chain_id: String,
-  process_id: String,
-  status: u32,
-  updated_at: String,
+/// Status of a request processed by Perfect Memory
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PmRequestStatus {
+  Pending(u32),
+  Success(u32),
+  Error(PmRequestError),
+  Unknown(u32),
+}
+
+impl From<u32> for PmRequestStatus {
+  fn from(status: u32) -> Self {
+    match status {
+      100 | 110 | 120 => PmRequestStatus::Pending(status),
+      200 | 300 => PmRequestStatus::Success(status),
+      400 => PmRequestStatus::Error(PmRequestError::Error),
+      401 => PmRequestStatus::Error(PmRequestError::ChildProcessError),
+      408 => PmRequestStatus::Error(PmRequestError::ServiceError),
+      410 => PmRequestStatus::Error(PmRequestError::ItemDisabled),
+      414 => PmRequestStatus::Error(PmRequestError::ItemNotFound),
+      421 => PmRequestStatus::Error(PmRequestError::InvalidScript),
+      422 => PmRequestStatus::Error(PmRequestError::InvalidIo),
+      423 => PmRequestStatus::Error(PmRequestError::InvalidStatus),
+      428 => PmRequestStatus::Error(PmRequestError::ProcessDisrupted),
+      500 => PmRequestStatus::Error(PmRequestError::UnexpectedError),
+      503 => PmRequestStatus::Error(PmRequestError::ServiceUnreachable),
+      _ => PmRequestStatus::Unknown(status),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PmRequestError {
+  Error,
+  ChildProcessError,
+  ServiceError,
+  ItemDisabled,
+  ItemNotFound,
+  InvalidScript,
+  InvalidIo,
+  InvalidStatus,
+  ProcessDisrupted,
+  UnexpectedError,
+  ServiceUnreachable,
+}
+
+impl PmRequestError {
+  pub(crate) fn get_message(&self) -> &'static str {
+    match self {
+      PmRequestError::Error => "Error: Request/Process has finished with an error",
+      PmRequestError::ChildProcessError => {
+        "Error on child process: Process has finished with an error on one of its children"
+      }
+      PmRequestError::ServiceError => "Error Service: Process has finished with a specific error",
+      PmRequestError::ItemDisabled => "Item Disabled: The item is disabled",
+      PmRequestError::ItemNotFound => "Item Not Found: The item is not found",
+      PmRequestError::InvalidScript => {
+        "Invalid Script: There was an error while running the script"
+      }
+      PmRequestError::InvalidIo => "Invalid I/O: The input or the output is invalid",
+      PmRequestError::InvalidStatus => {
+        "Invalid Status: The process has been stopped with an invalid status"
+      }
+      PmRequestError::ProcessDisrupted => "Process disrupted: Process has been manually disrupted",
+      PmRequestError::UnexpectedError => {
+        "Unexpected error: Service process has finished with an unknow error"
+      }
+      PmRequestError::ServiceUnreachable => "Service unreachable: Service could not be reached",
+    }
+  }
+}
+
+/// Publisher creating a Perfect Memory request for each event
+pub(crate) struct PerfectMemoryPublisher {
+  pub(crate) http_client: HttpClient,
+  pub(crate) config: PmConfig,
+}
+
+impl Publisher for PerfectMemoryPublisher {
+  fn publish(
+    &self,
+    job_result: JobResult,
+    channel: &Option<McaiChannel>,
+    event: &Event,
+    stop_signal: &StopSignal,
+  ) -> Result<JobResult> {
+    publish_to_perfect_memory(
+      job_result,
+      channel,
+      &self.http_client,
+      &self.config,
+      event,
+      stop_signal,
+    )
+  }
 }
 
 pub(crate) fn publish_to_perfect_memory(
   job_result: JobResult,
-  pm_client_id: &str,
-  pm_event_name: &str,
-  pm_api_key: &str,
-  pm_endpoint: &str,
-  triples: &str,
-) -> Result<()> {
-  let url = pm_endpoint.to_owned() + "/v1/requests";
-
-  let client = reqwest::blocking::Client::builder()
-    .build()
-    .map_err(|error| MessageError::ProcessingError(job_result.clone().with_error(error)))?;
+  channel: &Option<McaiChannel>,
+  http_client: &HttpClient,
+  config: &PmConfig,
+  event: &Event,
+  stop_signal: &StopSignal,
+) -> Result<JobResult> {
+  let url = config.endpoint.to_owned() + "/v1/requests";
 
   let body = PmRequestBody {
-    client_id: pm_client_id.to_owned(),
-    name: pm_event_name.to_owned(),
+    client_id: config.client_id.to_owned(),
+    name: event.name.to_owned(),
     inputs: Inputs {
       infos_graph: InfosGraph {
-        value: base64::encode(triples),
+        value: base64::encode(event.rdf),
         kind: "binary".to_string(),
       },
     },
   };
 
-  let response = client
+  // The request creation is not idempotent, retry it only if explicitly allowed
+  let idempotency = if config.retry_publication {
+    Idempotency::Idempotent
+  } else {
+    Idempotency::NonIdempotent
+  };
+
+  let request = http_client
     .post(url.as_str())
     .header(CACHE_CONTROL, "no-cache")
     .header(CONTENT_TYPE, "application/json")
-    .header("X-Api-Key", pm_api_key)
-    .json(&body)
-    .send()
-    .map_err(|e| {
-      MessageError::ProcessingError(
-        job_result
-          .clone()
-          .with_status(JobStatus::Error)
-          .with_message(&e.to_string()),
-      )
-    })?;
+    .header("X-Api-Key", &config.api_key)
+    .json(&body);
+
+  let response = http_client.send(request, idempotency).map_err(|e| {
+    MessageError::ProcessingError(
+      job_result
+        .clone()
+        .with_status(JobStatus::Error)
+        .with_message(&e),
+    )
+  })?;
 
   if response.status() != 201 {
     let text = response
@@ -124,126 +249,319 @@ pub(crate) fn publish_to_perfect_memory(
     )
   })?;
 
+  let job_result = job_result.with_parameters(&mut vec![Parameter {
+    id: "perfect_memory_location".to_string(),
+    kind: String::get_type_as_string(),
+    store: None,
+    value: Some(location_str.into()),
+    default: None,
+  }]);
+
+  if !config.wait_for_completion {
+    info!(
+      "Perfect Memory request created at {}, do not wait for its completion",
+      location_str
+    );
+    return Ok(job_result);
+  }
+
+  let mut headers = BTreeMap::new();
+  headers.insert("X-Api-Key".to_string(), config.api_key.clone());
+  let request = PolledRequest {
+    service: "Perfect Memory",
+    location: location_str,
+    headers: &headers,
+    response_parameter: "perfect_memory_response",
+    progression: event.progression,
+    disrupt_on_stop: config.disrupt_on_stop,
+  };
+  wait_for_request(
+    job_result,
+    channel,
+    http_client,
+    config,
+    &request,
+    stop_signal,
+  )
+}
+
+/// Request processed asynchronously after its creation, its status being given at `location`
+pub(crate) struct PolledRequest<'a> {
+  /// Name of the service processing the request, in the messages
+  pub(crate) service: &'a str,
+  pub(crate) location: &'a str,
+  /// Headers of the status requests
+  pub(crate) headers: &'a BTreeMap<String, String>,
+  /// Job result parameter set with the final status response
+  pub(crate) response_parameter: &'a str,
+  /// Part of the job progression moved through while polling
+  pub(crate) progression: ProgressionRange,
+  /// Set the 428 status of the request when the job is stopped
+  pub(crate) disrupt_on_stop: bool,
+}
+
+/// Poll the status of a request until it ends, the JSON response having a Perfect Memory
+/// `status` code
+pub(crate) fn wait_for_request(
+  job_result: JobResult,
+  channel: &Option<McaiChannel>,
+  http_client: &HttpClient,
+  config: &PmConfig,
+  request: &PolledRequest,
+  stop_signal: &StopSignal,
+) -> Result<JobResult> {
+  let location = request.location;
+  let mut poller = Poller::new(config);
+  let mut progression = request.progression.start;
+
   loop {
-    let response = client
-      .get(location_str)
-      .header("X-Api-Key", pm_api_key)
-      .send()
+    if stop_signal.is_raised() {
+      info!(
+        "Stop requested while waiting for {} request completion",
+        request.service
+      );
+      if request.disrupt_on_stop {
+        disrupt_request(http_client, request);
+      }
+      return Err(MessageError::ProcessingError(
+        job_result
+          .with_status(JobStatus::Error)
+          .with_message(&format!(
+            "Stopped: the job has been stopped before the end of {} request {}",
+            request.service, location
+          )),
+      ));
+    }
+
+    // The request duration is unknown, move forward by one point on each poll
+    progression = (progression + 1).min(request.progression.end);
+    publish_job_progression(channel.clone(), job_result.get_job_id(), progression)?;
+
+    let mut status_request = http_client.get(location);
+    for (name, value) in request.headers {
+      status_request = status_request.header(name.as_str(), value.as_str());
+    }
+
+    let response = http_client
+      .send(status_request, Idempotency::Idempotent)
       .map_err(|e| {
         MessageError::ProcessingError(
           job_result
             .clone()
             .with_status(JobStatus::Error)
-            .with_message(&e.to_string()),
+            .with_message(&e),
         )
       })?;
 
     if response.status() != 200 {
-      let ten_seconds = time::Duration::from_secs(10);
-      thread::sleep(ten_seconds);
+      error!(
+        "Unable to get {} request status: {}",
+        request.service,
+        response.status()
+      );
+      poller.wait(request, job_result.clone(), stop_signal)?;
       continue;
     }
 
-    let resp_body: PmResponseBody = response.json().map_err(|error| {
+    let status = response.json::<Value>().ok().and_then(|resp_body| {
+      let status = resp_body.get("status")?.as_u64()?;
+      Some((resp_body, status as u32))
+    });
+    let (resp_body, status) = status.ok_or_else(|| {
       MessageError::ProcessingError(
         job_result
           .clone()
           .with_status(JobStatus::Error)
           .with_message(&format!(
-            "Unknown error: unable to get status from Perfect Memory platform: {:?}",
-            error
+            "Unknown error: unable to get status from {} platform",
+            request.service
           )),
       )
     })?;
-    error!("Perfect Memory response: {:?}", resp_body);
-    match resp_body.status {
-      200 | 300 => {
-        return Ok(());
-      }
-      100 | 110 | 120 => {}
-      400 => {
-        return Err(MessageError::ProcessingError(
-          job_result
-            .with_status(JobStatus::Error)
-            .with_message("Error: Request/Process has finished with an error"),
-        ));
-      }
-      401 => {
-        return Err(MessageError::ProcessingError(
-          job_result.with_status(JobStatus::Error).with_message(
-            "Error on child process: Process has finished with an error on one of its children",
-          ),
-        ));
-      }
-      408 => {
-        return Err(MessageError::ProcessingError(
-          job_result
-            .with_status(JobStatus::Error)
-            .with_message("Error Service: Process has finished with a specific error"),
-        ));
-      }
-      410 => {
-        return Err(MessageError::ProcessingError(
-          job_result
-            .with_status(JobStatus::Error)
-            .with_message("Item Disabled: The item is disabled"),
-        ));
-      }
-      414 => {
-        return Err(MessageError::ProcessingError(
-          job_result
-            .with_status(JobStatus::Error)
-            .with_message("Item Not Found: The item is not found"),
-        ));
-      }
-      421 => {
-        return Err(MessageError::ProcessingError(
-          job_result
-            .with_status(JobStatus::Error)
-            .with_message("Invalid Script: There was an error while running the script"),
-        ));
-      }
-      422 => {
-        return Err(MessageError::ProcessingError(
-          job_result
-            .with_status(JobStatus::Error)
-            .with_message("Invalid I/O: The input or the output is invalid"),
-        ));
-      }
-      423 => {
-        return Err(MessageError::ProcessingError(
-          job_result
-            .with_status(JobStatus::Error)
-            .with_message("Invalid Status: The process has been stopped with an invalid status"),
-        ));
+    info!("{} response: {}", request.service, resp_body);
+
+    match PmRequestStatus::from(status) {
+      PmRequestStatus::Success(_) => {
+        return job_result
+          .with_json(request.response_parameter, &resp_body)
+          .map_err(MessageError::RuntimeError);
       }
-      428 => {
-        return Err(MessageError::ProcessingError(
-          job_result
-            .with_status(JobStatus::Error)
-            .with_message("Process disrupted: Process has been manually disrupted"),
-        ));
+      PmRequestStatus::Error(request_error) => {
+        let job_result = job_result
+          .with_status(JobStatus::Error)
+          .with_message(request_error.get_message())
+          .with_json(request.response_parameter, &resp_body)
+          .map_err(MessageError::RuntimeError)?;
+        return Err(MessageError::ProcessingError(job_result));
       }
-      500 => {
-        return Err(MessageError::ProcessingError(
-          job_result
-            .with_status(JobStatus::Error)
-            .with_message("Unexpected error: Service process has finished with an unknow error"),
-        ));
+      PmRequestStatus::Pending(_) => {}
+      PmRequestStatus::Unknown(status) => {
+        error!("Unknown {} request status: {}", request.service, status);
       }
-      503 => {
-        return Err(MessageError::ProcessingError(
-          job_result
-            .with_status(JobStatus::Error)
-            .with_message("Service unreachable: Service could not be reached"),
-        ));
+    }
+
+    poller.wait(request, job_result.clone(), stop_signal)?;
+  }
+}
+
+/// Ask the service to disrupt a running request, the request ends with the 428 status
+fn disrupt_request(http_client: &HttpClient, request: &PolledRequest) {
+  let mut disrupt_request = http_client
+    .patch(request.location)
+    .header(CONTENT_TYPE, "application/json")
+    .json(&PmStatusUpdate { status: 428 });
+  for (name, value) in request.headers {
+    disrupt_request = disrupt_request.header(name.as_str(), value.as_str());
+  }
+
+  match http_client.send(disrupt_request, Idempotency::Idempotent) {
+    Ok(response) if response.status().is_success() => {
+      info!("{} request {} disrupted", request.service, request.location)
+    }
+    Ok(response) => error!(
+      "Unable to disrupt {} request {}: {}",
+      request.service,
+      request.location,
+      response.status()
+    ),
+    Err(error) => error!(
+      "Unable to disrupt {} request {}: {}",
+      request.service, request.location, error
+    ),
+  }
+}
+
+/// Paces the polling of a request, until a deadline is reached
+struct Poller {
+  deadline: Instant,
+  timeout: Duration,
+  interval: Duration,
+  max_interval: Duration,
+}
+
+impl Poller {
+  fn new(config: &PmConfig) -> Self {
+    Poller {
+      deadline: Instant::now() + config.timeout,
+      timeout: config.timeout,
+      interval: config.poll_interval,
+      max_interval: config.max_poll_interval.max(config.poll_interval),
+    }
+  }
+
+  /// Delay before the next poll, doubling the interval each time up to the maximum one
+  fn next_delay(&mut self, now: Instant) -> Option<Duration> {
+    if now >= self.deadline {
+      return None;
+    }
+
+    let delay = self.interval.min(self.deadline - now);
+    self.interval = (self.interval * 2).min(self.max_interval);
+    Some(delay)
+  }
+
+  /// Sleep until the next poll, returning early if a stop is requested
+  fn wait(
+    &mut self,
+    request: &PolledRequest,
+    job_result: JobResult,
+    stop_signal: &StopSignal,
+  ) -> Result<()> {
+    let delay = self.next_delay(Instant::now()).ok_or_else(|| {
+      MessageError::ProcessingError(job_result.with_status(JobStatus::Error).with_message(
+        &format!(
+          "Timeout: {} request {} did not complete within {} seconds",
+          request.service,
+          request.location,
+          self.timeout.as_secs()
+        ),
+      ))
+    })?;
+
+    let wake_up = Instant::now() + delay;
+    while !stop_signal.is_raised() {
+      let now = Instant::now();
+      if now >= wake_up {
+        break;
       }
-      _ => {}
+      thread::sleep((wake_up - now).min(Duration::from_secs(1)));
     }
+    Ok(())
+  }
+}
+
+#[test]
+fn test_pm_request_status() {
+  assert_eq!(PmRequestStatus::from(110), PmRequestStatus::Pending(110));
+  assert_eq!(PmRequestStatus::from(300), PmRequestStatus::Success(300));
+  assert_eq!(
+    PmRequestStatus::from(428),
+    PmRequestStatus::Error(PmRequestError::ProcessDisrupted)
+  );
+  assert_eq!(PmRequestStatus::from(999), PmRequestStatus::Unknown(999));
+}
+
+#[test]
+fn test_wait_for_stopped_request() {
+  use crate::http::{start_test_server, RetryPolicy};
+
+  let (address, server) = start_test_server(1, 200);
+  let http_client = HttpClient::new(RetryPolicy::default()).unwrap();
+  let config = PmConfig {
+    disrupt_on_stop: true,
+    ..PmConfig::from(RdfWorkerParameters::default())
+  };
+  let location = format!("{}/v1/requests/1", address);
+  let headers = BTreeMap::new();
+  let request = PolledRequest {
+    service: "Perfect Memory",
+    location: &location,
+    headers: &headers,
+    response_parameter: "perfect_memory_response",
+    progression: ProgressionRange::publication(0, 1),
+    disrupt_on_stop: config.disrupt_on_stop,
+  };
+  let stop_signal = StopSignal::default();
+  stop_signal.raise();
 
-    let ten_seconds = time::Duration::from_secs(10);
-    thread::sleep(ten_seconds);
+  match wait_for_request(
+    JobResult::new(0),
+    &None,
+    &http_client,
+    &config,
+    &request,
+    &stop_signal,
+  ) {
+    Err(MessageError::ProcessingError(job_result)) => {
+      assert_eq!(job_result.get_status(), &JobStatus::Error)
+    }
+    result => panic!("unexpected result {:?}", result),
   }
+
+  let requests = server.join().unwrap();
+  assert!(requests[0].starts_with("PATCH /v1/requests/1 HTTP/1.1\r\n"));
+  assert!(requests[0].ends_with(r#"{"status":428}"#));
+}
+
+#[test]
+fn test_poller_delays() {
+  let now = Instant::now();
+  let mut poller = Poller {
+    deadline: now + Duration::from_secs(100),
+    timeout: Duration::from_secs(100),
+    interval: Duration::from_secs(10),
+    max_interval: Duration::from_secs(30),
+  };
+
+  assert_eq!(poller.next_delay(now), Some(Duration::from_secs(10)));
+  assert_eq!(poller.next_delay(now), Some(Duration::from_secs(20)));
+  assert_eq!(poller.next_delay(now), Some(Duration::from_secs(30)));
+  assert_eq!(poller.next_delay(now), Some(Duration::from_secs(30)));
+  assert_eq!(
+    poller.next_delay(now + Duration::from_secs(95)),
+    Some(Duration::from_secs(5))
+  );
+  assert_eq!(poller.next_delay(now + Duration::from_secs(100)), None);
 }
 
 // #[test]
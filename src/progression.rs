@@ -0,0 +1,41 @@
+// Job progression published at each processing stage
+
+pub(crate) const PROGRESSION_FETCH_METADATA: u8 = 10;
+pub(crate) const PROGRESSION_FETCH_FILES: u8 = 25;
+pub(crate) const PROGRESSION_CONVERT: u8 = 50;
+pub(crate) const PROGRESSION_VALIDATE: u8 = 60;
+pub(crate) const PROGRESSION_PUBLISH: u8 = 70;
+
+/// Part of the job progression covered by the publication of an event
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ProgressionRange {
+  pub(crate) start: u8,
+  pub(crate) end: u8,
+}
+
+impl ProgressionRange {
+  /// Range of the publication of the event `index` out of `count`, the events of all the targets
+  /// sharing the progression from `PROGRESSION_PUBLISH` to 99
+  pub(crate) fn publication(index: usize, count: usize) -> Self {
+    let span = usize::from(99 - PROGRESSION_PUBLISH);
+    let count = count.max(1);
+    let at = |index: usize| PROGRESSION_PUBLISH + (index.min(count) * span / count) as u8;
+    ProgressionRange {
+      start: at(index),
+      end: at(index + 1),
+    }
+  }
+}
+
+#[test]
+fn test_publication_range() {
+  assert_eq!(
+    ProgressionRange::publication(0, 1),
+    ProgressionRange { start: 70, end: 99 }
+  );
+  assert_eq!(
+    ProgressionRange::publication(1, 3),
+    ProgressionRange { start: 79, end: 89 }
+  );
+  assert_eq!(ProgressionRange::publication(2, 3).end, 99);
+}
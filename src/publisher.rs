@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use mcai_worker_sdk::{
+  info,
+  job::{JobResult, JobStatus},
+  JsonSchema, McaiChannel, MessageError, Result,
+};
+use rdf::graph::Graph;
+
+use crate::graph_store::GraphStoreMethod;
+use crate::progression::ProgressionRange;
+use crate::stop_signal::StopSignal;
+
+/// Destination of the RDF of a job, with its own serialization and credentials
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct PublicationTarget {
+  /// Name of the target in the job result, its type when not set
+  pub name: Option<String>,
+  /// Serialize the RDF as N-Triples instead of Turtle, like the job when not set
+  pub ntriples: Option<bool>,
+  #[serde(flatten)]
+  pub kind: TargetKind,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum TargetKind {
+  #[serde(rename = "perfect_memory")]
+  PerfectMemory {
+    endpoint: String,
+    username: String,
+    password: String,
+  },
+  #[serde(rename = "graph_store")]
+  GraphStore {
+    endpoint: String,
+    username: Option<String>,
+    password: Option<String>,
+    method: Option<GraphStoreMethod>,
+  },
+  #[serde(rename = "sparql_update")]
+  SparqlUpdate {
+    endpoint: String,
+    username: Option<String>,
+    password: Option<String>,
+  },
+  #[serde(rename = "file")]
+  File { directory: String },
+  #[serde(rename = "webhook")]
+  Webhook {
+    url: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    /// Body with `{{rdf}}`, `{{rdf_base64}}`, `{{reference}}`, `{{job_id}}`, `{{event}}` and
+    /// `{{format}}` placeholders, a JSON object of these values when not set
+    body_template: Option<String>,
+    /// Content type of the body, `application/json` when not set
+    content_type: Option<String>,
+    /// Status codes of a successful response, any success status when not set
+    expected_statuses: Option<Vec<u16>>,
+  },
+}
+
+impl TargetKind {
+  pub fn get_type(&self) -> &'static str {
+    match self {
+      TargetKind::PerfectMemory { .. } => "perfect_memory",
+      TargetKind::GraphStore { .. } => "graph_store",
+      TargetKind::SparqlUpdate { .. } => "sparql_update",
+      TargetKind::File { .. } => "file",
+      TargetKind::Webhook { .. } => "webhook",
+    }
+  }
+}
+
+/// Behaviour of a job when a publication target fails
+#[derive(Clone, Copy, Debug, Default, Deserialize, JsonSchema, PartialEq)]
+pub enum FailurePolicy {
+  /// Stop publishing at the first failing target
+  #[default]
+  #[serde(rename = "fail_fast")]
+  FailFast,
+  /// Publish to all the targets, the job failing at the end if any of them failed
+  #[serde(rename = "best_effort")]
+  BestEffort,
+}
+
+/// Change of the published graph of a reference carried by an event
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum EventKind {
+  /// Add the triples to the graph
+  Insert,
+  /// Remove the triples from the graph
+  Delete,
+  /// Replace the whole graph by the triples
+  Replace,
+  /// Remove the whole graph, the triples being the last published ones when known
+  Retract,
+}
+
+/// Graph to publish, with its serialization
+#[derive(Debug)]
+pub(crate) struct Event<'a> {
+  /// Reference of the video, not set for the merged graph of a batch
+  pub(crate) reference: Option<&'a str>,
+  pub(crate) kind: EventKind,
+  /// Perfect Memory event name
+  pub(crate) name: &'a str,
+  pub(crate) graph: &'a Graph,
+  /// Graph serialized as N-Triples or Turtle, following `n_triples`
+  pub(crate) rdf: &'a str,
+  pub(crate) n_triples: bool,
+  /// Part of the job progression reported while the event is published
+  pub(crate) progression: ProgressionRange,
+}
+
+/// Destination of the RDF produced by a job
+pub(crate) trait Publisher: Send + Sync {
+  /// Tells whether some triples of a graph can be deleted, otherwise updates replace the whole
+  /// graph of the reference
+  fn publishes_diff(&self) -> bool {
+    true
+  }
+
+  fn publish(
+    &self,
+    job_result: JobResult,
+    channel: &Option<McaiChannel>,
+    event: &Event,
+    stop_signal: &StopSignal,
+  ) -> Result<JobResult>;
+}
+
+/// Publisher writing the RDF of each event in a directory
+pub(crate) struct FilePublisher {
+  pub(crate) directory: PathBuf,
+}
+
+impl Publisher for FilePublisher {
+  fn publish(
+    &self,
+    job_result: JobResult,
+    _channel: &Option<McaiChannel>,
+    event: &Event,
+    _stop_signal: &StopSignal,
+  ) -> Result<JobResult> {
+    let path = write_output_file(
+      &self.directory,
+      job_result.get_job_id(),
+      event.reference.unwrap_or("batch"),
+      event.name,
+      event.rdf,
+      event.n_triples,
+    )
+    .map_err(|error| {
+      MessageError::ProcessingError(
+        job_result
+          .clone()
+          .with_status(JobStatus::Error)
+          .with_message(&error),
+      )
+    })?;
+
+    Ok(job_result.with_destination_paths(&mut vec![path]))
+  }
+}
+
+/// Write the RDF of an event in `<directory>/<job id>_<name>_<event>.<nt|ttl>`, and return the
+/// path of the file
+pub(crate) fn write_output_file(
+  directory: &Path,
+  job_id: u64,
+  name: &str,
+  event: &str,
+  rdf: &str,
+  n_triples: bool,
+) -> std::result::Result<String, String> {
+  let extension = if n_triples { "nt" } else { "ttl" };
+  let path = directory.join(format!("{}_{}_{}.{}", job_id, name, event, extension));
+  info!("Write RDF to {}", path.display());
+
+  fs::create_dir_all(directory)
+    .and_then(|_| fs::write(&path, rdf))
+    .map_err(|error| format!("Unable to write {}: {}", path.display(), error))?;
+  Ok(path.to_string_lossy().to_string())
+}
+
+#[test]
+fn test_publication_target() {
+  let target: PublicationTarget = serde_json::from_str(
+    r#"{"type": "graph_store", "name": "fuseki", "endpoint": "http://localhost:3030/ds/data", "method": "post"}"#,
+  )
+  .unwrap();
+  assert_eq!(target.name, Some("fuseki".to_string()));
+  assert_eq!(target.kind.get_type(), "graph_store");
+  match target.kind {
+    TargetKind::GraphStore { method, .. } => assert_eq!(method, Some(GraphStoreMethod::Post)),
+    kind => panic!("unexpected target {:?}", kind),
+  }
+
+  let schema = serde_json::to_string(&schemars::schema_for!(PublicationTarget)).unwrap();
+  assert!(schema.contains("sparql_update"));
+}
@@ -1,4 +1,6 @@
 use crate::namespaces::*;
+use std::collections::BTreeMap;
+
 use rdf::{graph::Graph, node::Node, triple::Triple, uri::Uri};
 
 pub(crate) fn add_link(
@@ -21,19 +23,66 @@ pub(crate) fn add_link(
     graph.create_literal_node(object.to_string())
   };
 
-  let triple = Triple::new(&subject_node, &predicate_node, &object_node);
+  let triple = Triple::new(subject_node, &predicate_node, &object_node);
   graph.add_triple(&triple);
 }
 
+/// Add the triples of `source` to `graph`, with new blank nodes so that they do not collide with
+/// the blank nodes of `graph`
+pub(crate) fn merge_graph(graph: &mut Graph, source: &Graph) {
+  let mut blank_nodes: BTreeMap<String, Node> = BTreeMap::new();
+
+  for triple in source.triples_iter() {
+    let subject = rename_blank_node(graph, &mut blank_nodes, triple.subject());
+    let object = rename_blank_node(graph, &mut blank_nodes, triple.object());
+    graph.add_triple(&Triple::new(&subject, triple.predicate(), &object));
+  }
+}
+
+fn rename_blank_node(
+  graph: &mut Graph,
+  blank_nodes: &mut BTreeMap<String, Node>,
+  node: &Node,
+) -> Node {
+  match node {
+    Node::BlankNode { id } => blank_nodes
+      .entry(id.clone())
+      .or_insert_with(|| graph.create_blank_node())
+      .clone(),
+    _ => node.clone(),
+  }
+}
+
+/// URI, literal value or blank node label of a node
+pub(crate) fn get_node_value(node: &Node) -> String {
+  match node {
+    Node::UriNode { uri } => uri.to_string().clone(),
+    Node::LiteralNode { literal, .. } => literal.clone(),
+    Node::BlankNode { id } => format!("_:{}", id),
+  }
+}
+
 pub(crate) fn add_related_node(graph: &mut Graph, subject_node: &Node, predicate: &str) -> Node {
   let blank = graph.create_blank_node();
   let predicate_node = graph.create_uri_node(&Uri::new(predicate.to_string()));
 
-  let triple = Triple::new(&subject_node, &predicate_node, &blank);
+  let triple = Triple::new(subject_node, &predicate_node, &blank);
   graph.add_triple(&triple);
   blank
 }
 
+pub(crate) fn add_node_link(
+  graph: &mut Graph,
+  subject_node: &Node,
+  predicate: &str,
+  object_node: &Node,
+) {
+  let predicate_node = graph.create_uri_node(&Uri::new(predicate.to_string()));
+
+  let triple = Triple::new(subject_node, &predicate_node, object_node);
+  graph.add_triple(&triple);
+}
+
 pub(crate) fn insert_identifier(
   graph: &mut Graph,
   subject_node: &Node,
@@ -47,7 +96,7 @@ pub(crate) fn insert_identifier(
 
   let o_identifier = EBUCORE_NAMESPACE.to_owned() + "Identifier";
 
-  let s_identifier = add_related_node(graph, &subject_node, &p_has_idenfitier);
+  let s_identifier = add_related_node(graph, subject_node, &p_has_idenfitier);
   add_link(
     graph,
     &s_identifier,
@@ -89,3 +138,23 @@ pub(crate) fn add_triple(graph: &mut Graph, subject: &str, predicate: &str, obje
   graph.add_triple(&triple);
   subject_node
 }
+
+#[test]
+fn test_merge_graph() {
+  use crate::convert::read_n_triples;
+
+  let content = r#"<http://example.com/a> <http://example.com/p> _:b0 .
+_:b0 <http://example.com/q> "value" .
+"#;
+  let mut graph = read_n_triples(content).unwrap();
+  let source = read_n_triples(content).unwrap();
+  merge_graph(&mut graph, &source);
+
+  assert_eq!(graph.count(), 4);
+  let blank_nodes: std::collections::BTreeSet<String> = graph
+    .triples_iter()
+    .map(|triple| get_node_value(triple.object()))
+    .filter(|value| value.starts_with("_:"))
+    .collect();
+  assert_eq!(blank_nodes.len(), 2);
+}
@@ -0,0 +1,176 @@
+use uuid::Uuid;
+
+use super::{AudioTrack, ExternalIds, Format, Resource, SubtitleStats, VideoTrack};
+use crate::checksum::FileDigest;
+use crate::storage_profile::StorageProfile;
+
+/// Builds the resource of a file delivered outside of the video factory
+pub struct ResourceBuilder {
+  filename: Option<String>,
+  format: Format,
+  storage: String,
+  url_prefix: Option<String>,
+  reference: Option<String>,
+  tags: Vec<String>,
+  digest: Option<FileDigest>,
+  bitrate_kbps: Option<u64>,
+  width: Option<u16>,
+  height: Option<u16>,
+  lang: Option<String>,
+  duration: Option<String>,
+  video_tracks: Vec<VideoTrack>,
+  audio_tracks: Vec<AudioTrack>,
+  renditions: Vec<Resource>,
+  subtitle_stats: Option<SubtitleStats>,
+}
+
+impl ResourceBuilder {
+  /// Builder of a resource without file, like a rendition described by a manifest
+  pub fn new(format: Format) -> Self {
+    ResourceBuilder {
+      filename: None,
+      format,
+      storage: String::new(),
+      url_prefix: None,
+      reference: None,
+      tags: vec![],
+      digest: None,
+      bitrate_kbps: None,
+      width: None,
+      height: None,
+      lang: None,
+      duration: None,
+      video_tracks: vec![],
+      audio_tracks: vec![],
+      renditions: vec![],
+      subtitle_stats: None,
+    }
+  }
+
+  pub fn filename(mut self, filename: &str) -> Self {
+    self.filename = Some(filename.to_string());
+    self
+  }
+
+  pub fn storage(mut self, storage: &str) -> Self {
+    self.storage = storage.to_string();
+    self
+  }
+
+  pub fn url_prefix(mut self, url_prefix: &str) -> Self {
+    self.url_prefix = Some(url_prefix.to_string());
+    self
+  }
+
+  /// Storage and URL prefix of the profile
+  pub fn storage_profile(self, profile: &StorageProfile) -> Self {
+    self
+      .storage(&profile.storage)
+      .url_prefix(&profile.url_prefix)
+  }
+
+  /// Reference of the video the resource belongs to
+  pub fn reference(mut self, reference: &str) -> Self {
+    self.reference = Some(reference.to_string());
+    self
+  }
+
+  pub fn tags(mut self, tags: Vec<String>) -> Self {
+    self.tags = tags;
+    self
+  }
+
+  /// Size and checksums of the file content
+  pub fn digest(mut self, digest: FileDigest) -> Self {
+    self.digest = Some(digest);
+    self
+  }
+
+  pub fn bitrate_kbps(mut self, bitrate_kbps: u64) -> Self {
+    self.bitrate_kbps = Some(bitrate_kbps);
+    self
+  }
+
+  pub fn dimensions(mut self, width: u16, height: u16) -> Self {
+    self.width = Some(width);
+    self.height = Some(height);
+    self
+  }
+
+  pub fn lang(mut self, lang: &str) -> Self {
+    self.lang = Some(lang.to_string());
+    self
+  }
+
+  /// Duration as an ISO 8601 duration, like `PT1M30.5S`
+  pub fn duration(mut self, duration: &str) -> Self {
+    self.duration = Some(duration.to_string());
+    self
+  }
+
+  pub fn video_track(mut self, video_track: VideoTrack) -> Self {
+    self.video_tracks.push(video_track);
+    self
+  }
+
+  pub fn audio_track(mut self, audio_track: AudioTrack) -> Self {
+    self.audio_tracks.push(audio_track);
+    self
+  }
+
+  /// Resources delivered through this one, like the representations of a manifest
+  pub fn renditions(mut self, renditions: Vec<Resource>) -> Self {
+    self.renditions = renditions;
+    self
+  }
+
+  pub fn subtitle_stats(mut self, subtitle_stats: SubtitleStats) -> Self {
+    self.subtitle_stats = Some(subtitle_stats);
+    self
+  }
+
+  pub fn build(self) -> Resource {
+    let url = match (&self.url_prefix, &self.filename) {
+      (Some(url_prefix), Some(filename)) => Some(format!("{}{}", url_prefix, filename)),
+      _ => None,
+    };
+
+    Resource {
+      id: Uuid::new_v4().to_urn().to_string(),
+      created_via: "Media-IO".to_string(),
+      format: self.format,
+      storage: self.storage,
+      path: None,
+      filename: self.filename,
+      audio_tracks: self.audio_tracks,
+      text_tracks: vec![],
+      video_tracks: self.video_tracks,
+      created_at: None,
+      updated_at: None,
+      ratio: None,
+      width: self.width,
+      height: self.height,
+      index: None,
+      copyright: None,
+      filesize_bytes: self.digest.as_ref().map(|digest| digest.size),
+      bitrate_kbps: self.bitrate_kbps,
+      md5_checksum: self.digest.as_ref().map(|digest| digest.md5.clone()),
+      sha256_checksum: self.digest.and_then(|digest| digest.sha256),
+      tags: self.tags,
+      url,
+      version: None,
+      lang: self.lang,
+      duration: self.duration,
+      renditions: self.renditions,
+      subtitle_stats: self.subtitle_stats,
+      inspected: true,
+      external_ids: ExternalIds {
+        video_id: self.reference,
+        legacy_id: None,
+        group_id: None,
+        job_id: None,
+        remote_id: None,
+      },
+    }
+  }
+}
@@ -1,12 +1,19 @@
 use crate::convert::ToRdf;
+use crate::inspection::{format_duration, Cue};
 use crate::namespaces::*;
 use crate::rdf_graph::{add_link, add_related_node};
+use mcai_worker_sdk::JsonSchema;
 use rdf::{
   error::{Error, ErrorType},
   graph::Graph,
+  node::Node,
   uri::Uri,
 };
 
+mod builder;
+
+pub use builder::ResourceBuilder;
+
 #[derive(Debug, Default, Deserialize)]
 pub struct Resources {
   #[serde(default)]
@@ -29,7 +36,9 @@ pub struct Resource {
   pub video_tracks: Vec<VideoTrack>,
   #[serde(default)]
   pub audio_tracks: Vec<AudioTrack>,
+  // Fields allowed as dead code below come from the video factory but are not published
   #[serde(default)]
+  #[allow(dead_code)]
   pub text_tracks: Vec<TextTrack>,
   pub id: String,
   pub format: Format,
@@ -37,21 +46,41 @@ pub struct Resource {
   pub path: Option<String>,
   pub filename: Option<String>,
   pub md5_checksum: Option<String>,
+  pub sha256_checksum: Option<String>,
   pub filesize_bytes: Option<u64>,
   pub external_ids: ExternalIds,
   #[serde(default)]
   pub tags: Vec<String>,
   pub created_via: String,
+  #[allow(dead_code)]
   pub version: Option<String>,
   pub created_at: Option<String>,
   pub updated_at: Option<String>,
   pub url: Option<String>,
   pub lang: Option<String>,
+  #[allow(dead_code)]
   pub ratio: Option<String>,
   pub width: Option<u16>,
   pub height: Option<u16>,
+  #[allow(dead_code)]
   pub index: Option<u64>,
+  #[allow(dead_code)]
   pub copyright: Option<String>,
+  pub duration: Option<String>,
+  #[serde(skip)]
+  pub renditions: Vec<Resource>,
+  #[serde(skip)]
+  pub subtitle_stats: Option<SubtitleStats>,
+  /// Described by the worker rather than by the video factory: its tracks are published, and its
+  /// bit rate as `bitRate`
+  #[serde(skip)]
+  pub inspected: bool,
+}
+
+impl Resource {
+  pub fn builder(filename: &str, format: Format) -> ResourceBuilder {
+    ResourceBuilder::new(format).filename(filename)
+  }
 }
 
 #[derive(Debug, Deserialize)]
@@ -67,16 +96,50 @@ pub struct VideoTrack {
 pub struct AudioTrack {
   pub codec_rfc6381: Option<String>,
   pub bitrate_kbps: u64,
-  pub sample_rate_hz: u16,
+  pub sample_rate_hz: u32,
   pub lang: String,
+  #[serde(default)]
+  pub channels: Option<u16>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct TextTrack {}
 
-#[derive(Debug, Deserialize)]
+/// Coverage of a subtitles file
+#[derive(Debug, PartialEq)]
+pub struct SubtitleStats {
+  /// Begin of the first cue, in seconds
+  pub first_cue_secs: f64,
+  /// End of the last cue, in seconds
+  pub last_cue_secs: f64,
+  pub cue_count: usize,
+  /// Whether the subtitles are for the deaf and hard of hearing
+  pub sdh: bool,
+}
+
+impl SubtitleStats {
+  pub fn new(cues: &[Cue], sdh: bool) -> Self {
+    SubtitleStats {
+      first_cue_secs: cues
+        .iter()
+        .map(|cue| cue.begin)
+        .fold(None, |first: Option<f64>, begin| {
+          Some(first.map_or(begin, |first| first.min(begin)))
+        })
+        .unwrap_or_default(),
+      last_cue_secs: cues.iter().map(|cue| cue.end).fold(0.0, f64::max),
+      cue_count: cues.len(),
+      sdh,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize)]
 pub struct Format {
+  // Only the type and mime type of a format are published
+  #[allow(dead_code)]
   pub id: String,
+  #[allow(dead_code)]
   pub label: String,
   #[serde(rename = "type")]
   pub kind: String,
@@ -86,14 +149,51 @@ pub struct Format {
 #[derive(Debug, Deserialize)]
 pub struct ExternalIds {
   pub video_id: Option<String>,
+  // Only the video of a file is published
+  #[allow(dead_code)]
   pub legacy_id: Option<String>,
+  #[allow(dead_code)]
   pub group_id: Option<String>,
+  #[allow(dead_code)]
   pub job_id: Option<String>,
+  #[allow(dead_code)]
   pub remote_id: Option<String>,
 }
 
+/// Server-side filters applied when listing the files of a video.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema)]
+pub struct ResourceFilter {
+  pub format_type: Option<String>,
+  #[serde(default)]
+  pub tags: Vec<String>,
+  pub storage: Option<String>,
+}
+
+impl ResourceFilter {
+  pub fn to_query(&self) -> Vec<(&str, String)> {
+    let mut query = vec![];
+    if let Some(ref format_type) = self.format_type {
+      query.push(("format.type", format_type.clone()));
+    }
+    for tag in &self.tags {
+      query.push(("tags", tag.clone()));
+    }
+    if let Some(ref storage) = self.storage {
+      query.push(("storage", storage.clone()));
+    }
+    query
+  }
+}
+
 impl ToRdf for Resource {
   fn to_rdf(&self, graph: &mut Graph) -> Result<(), Error> {
+    self.add_to_graph(graph).map(|_| ())
+  }
+}
+
+impl Resource {
+  /// Describe the resource in a node related to its video, and return this node
+  pub fn add_to_graph(&self, graph: &mut Graph) -> Result<Node, Error> {
     let video_id = self.external_ids.video_id.clone().ok_or_else(|| {
       Error::new(
         ErrorType::InvalidWriterOutput,
@@ -103,9 +203,16 @@ impl ToRdf for Resource {
     let s_root = format!("http://resources.idfrancetv.fr/medias/{}", video_id);
     let root_node = graph.create_uri_node(&Uri::new(s_root));
 
+    Ok(self.add_related_resource(graph, &root_node))
+  }
+
+  /// Describe the resource in a node related to `root_node`
+  fn add_related_resource(&self, graph: &mut Graph, root_node: &Node) -> Node {
     let p_has_creator = format!("{}hasCreator", EBUCORE_NAMESPACE);
     let p_date_created = format!("{}dateCreated", EBUCORE_NAMESPACE);
     let p_date_modified = format!("{}dateModified", EBUCORE_NAMESPACE);
+    let p_bit_rate = format!("{}bitRate", EBUCORE_NAMESPACE);
+    let p_duration = format!("{}durationNormalPlayTime", EBUCORE_NAMESPACE);
     let p_file_size = format!("{}fileSize", EBUCORE_NAMESPACE);
     let p_filename = format!("{}filename", EBUCORE_NAMESPACE);
     let p_has_format = format!("{}hasFormat", EBUCORE_NAMESPACE);
@@ -138,13 +245,13 @@ impl ToRdf for Resource {
 
     let s_has_related_object = match self.format.mime_type.as_str() {
       "image/jpeg" => {
-        let node = add_related_node(graph, &root_node, &p_has_related_image);
+        let node = add_related_node(graph, root_node, &p_has_related_image);
         add_link(graph, &node, &p_type, &o_picture, None, None, true);
 
         node
       }
       _ => {
-        let node = add_related_node(graph, &root_node, &p_has_related_resource);
+        let node = add_related_node(graph, root_node, &p_has_related_resource);
         add_link(graph, &node, &p_type, &o_media_resource, None, None, true);
         node
       }
@@ -164,7 +271,7 @@ impl ToRdf for Resource {
         graph,
         &s_has_related_object,
         &p_locator,
-        &url,
+        url,
         None,
         None,
         false,
@@ -185,7 +292,7 @@ impl ToRdf for Resource {
         graph,
         &s_has_related_object,
         &p_date_created,
-        &created_at,
+        created_at,
         None,
         Some(format!("{}dateTime", XML_NAMESPACE)),
         false,
@@ -196,7 +303,7 @@ impl ToRdf for Resource {
         graph,
         &s_has_related_object,
         &p_date_modified,
-        &updated_at,
+        updated_at,
         None,
         Some(format!("{}dateTime", XML_NAMESPACE)),
         false,
@@ -313,11 +420,29 @@ impl ToRdf for Resource {
       );
     }
 
-    if let Some(ref bitrate_kbps) = self.bitrate_kbps {
+    if let Some(ref sha256_checksum) = self.sha256_checksum {
       add_link(
         graph,
         &s_has_related_object,
         &p_hash_value,
+        &format!("urn:sha256:{}", sha256_checksum),
+        None,
+        None,
+        false,
+      );
+    }
+
+    if let Some(ref bitrate_kbps) = self.bitrate_kbps {
+      // The bit rate of the video factory files has always been published as a hash value
+      let p_bit_rate = if self.inspected {
+        &p_bit_rate
+      } else {
+        &p_hash_value
+      };
+      add_link(
+        graph,
+        &s_has_related_object,
+        p_bit_rate,
         &format!("{}", bitrate_kbps * 1000),
         None,
         Some(format!("{}nonNegativeInteger", XML_NAMESPACE)),
@@ -336,7 +461,7 @@ impl ToRdf for Resource {
         None,
         true,
       );
-      add_link(graph, &s_has_language, &p_label, &lang, None, None, false);
+      add_link(graph, &s_has_language, &p_label, lang, None, None, false);
     }
 
     let s_is_issued_by = add_related_node(graph, &s_has_related_object, &p_is_issued_by);
@@ -362,9 +487,250 @@ impl ToRdf for Resource {
     for tag in &self.tags {
       let s_has_topic = add_related_node(graph, &s_has_related_object, &p_has_topic);
       add_link(graph, &s_has_topic, &p_type, &o_tag, None, None, true);
-      add_link(graph, &s_has_topic, &p_pref_label, &tag, None, None, false);
+      add_link(graph, &s_has_topic, &p_pref_label, tag, None, None, false);
       add_link(graph, &s_has_topic, &p_definition, "Tag", None, None, true);
     }
-    Ok(())
+
+    if let Some(ref duration) = self.duration {
+      add_link(
+        graph,
+        &s_has_related_object,
+        &p_duration,
+        duration,
+        None,
+        Some(format!("{}duration", XML_NAMESPACE)),
+        false,
+      );
+    }
+
+    if self.inspected {
+      for video_track in &self.video_tracks {
+        video_track.add_track(graph, &s_has_related_object);
+      }
+      for audio_track in &self.audio_tracks {
+        audio_track.add_track(graph, &s_has_related_object);
+      }
+    }
+
+    if let Some(ref subtitle_stats) = self.subtitle_stats {
+      subtitle_stats.add_stats(graph, &s_has_related_object);
+    }
+
+    for rendition in &self.renditions {
+      rendition.add_related_resource(graph, &s_has_related_object);
+    }
+
+    s_has_related_object
+  }
+}
+
+impl SubtitleStats {
+  fn add_stats(&self, graph: &mut Graph, resource_node: &Node) {
+    let p_cue_count = format!("{}cueCount", FRANCETV_NAMESPACE);
+    let p_first_cue_time = format!("{}firstCueTime", FRANCETV_NAMESPACE);
+    let p_last_cue_time = format!("{}lastCueTime", FRANCETV_NAMESPACE);
+    let p_sdh = format!("{}hardOfHearing", FRANCETV_NAMESPACE);
+
+    add_link(
+      graph,
+      resource_node,
+      &p_cue_count,
+      &format!("{}", self.cue_count),
+      None,
+      Some(format!("{}nonNegativeInteger", XML_NAMESPACE)),
+      false,
+    );
+    if self.cue_count > 0 {
+      add_link(
+        graph,
+        resource_node,
+        &p_first_cue_time,
+        &format_duration(self.first_cue_secs),
+        None,
+        Some(format!("{}duration", XML_NAMESPACE)),
+        false,
+      );
+      add_link(
+        graph,
+        resource_node,
+        &p_last_cue_time,
+        &format_duration(self.last_cue_secs),
+        None,
+        Some(format!("{}duration", XML_NAMESPACE)),
+        false,
+      );
+    }
+    add_link(
+      graph,
+      resource_node,
+      &p_sdh,
+      &format!("{}", self.sdh),
+      None,
+      Some(format!("{}boolean", XML_NAMESPACE)),
+      false,
+    );
+  }
+}
+
+fn add_codec(graph: &mut Graph, track_node: &Node, codec: &str) {
+  let p_has_codec = format!("{}hasCodec", EBUCORE_NAMESPACE);
+  let p_codec_id = format!("{}codecId", EBUCORE_NAMESPACE);
+  let p_type = format!("{}type", RDF_NAMESPACE);
+  let o_codec = format!("{}Codec", EBUCORE_NAMESPACE);
+
+  let s_has_codec = add_related_node(graph, track_node, &p_has_codec);
+  add_link(graph, &s_has_codec, &p_type, &o_codec, None, None, true);
+  add_link(graph, &s_has_codec, &p_codec_id, codec, None, None, false);
+}
+
+impl VideoTrack {
+  fn add_track(&self, graph: &mut Graph, resource_node: &Node) {
+    let p_has_track = format!("{}hasTrack", EBUCORE_NAMESPACE);
+    let p_bit_rate = format!("{}bitRate", EBUCORE_NAMESPACE);
+    let p_frame_rate = format!("{}frameRate", EBUCORE_NAMESPACE);
+    let p_height = format!("{}height", EBUCORE_NAMESPACE);
+    let p_width = format!("{}width", EBUCORE_NAMESPACE);
+    let p_type = format!("{}type", RDF_NAMESPACE);
+    let o_video_track = format!("{}VideoTrack", EBUCORE_NAMESPACE);
+
+    let s_has_track = add_related_node(graph, resource_node, &p_has_track);
+    add_link(
+      graph,
+      &s_has_track,
+      &p_type,
+      &o_video_track,
+      None,
+      None,
+      true,
+    );
+
+    if let Some(ref codec) = self.codec_rfc6381 {
+      add_codec(graph, &s_has_track, codec);
+    }
+    if self.bitrate_kbps > 0 {
+      add_link(
+        graph,
+        &s_has_track,
+        &p_bit_rate,
+        &format!("{}", self.bitrate_kbps * 1000),
+        None,
+        Some(format!("{}nonNegativeInteger", XML_NAMESPACE)),
+        false,
+      );
+    }
+    if self.width > 0 && self.height > 0 {
+      add_link(
+        graph,
+        &s_has_track,
+        &p_width,
+        &format!("{}", self.width),
+        None,
+        Some(format!("{}integer", XML_NAMESPACE)),
+        false,
+      );
+      add_link(
+        graph,
+        &s_has_track,
+        &p_height,
+        &format!("{}", self.height),
+        None,
+        Some(format!("{}integer", XML_NAMESPACE)),
+        false,
+      );
+    }
+    if self.frame_rate_fps > 0 {
+      add_link(
+        graph,
+        &s_has_track,
+        &p_frame_rate,
+        &format!("{}", self.frame_rate_fps),
+        None,
+        Some(format!("{}integer", XML_NAMESPACE)),
+        false,
+      );
+    }
+  }
+}
+
+impl AudioTrack {
+  fn add_track(&self, graph: &mut Graph, resource_node: &Node) {
+    let p_has_track = format!("{}hasTrack", EBUCORE_NAMESPACE);
+    let p_audio_channel_number = format!("{}audioChannelNumber", EBUCORE_NAMESPACE);
+    let p_bit_rate = format!("{}bitRate", EBUCORE_NAMESPACE);
+    let p_has_language = format!("{}hasLanguage", EBUCORE_NAMESPACE);
+    let p_sample_rate = format!("{}sampleRate", EBUCORE_NAMESPACE);
+    let p_type = format!("{}type", RDF_NAMESPACE);
+    let p_label = format!("{}label", SKOS_NAMESPACE);
+    let o_audio_track = format!("{}AudioTrack", EBUCORE_NAMESPACE);
+    let o_language = format!("{}Language", EBUCORE_NAMESPACE);
+
+    let s_has_track = add_related_node(graph, resource_node, &p_has_track);
+    add_link(
+      graph,
+      &s_has_track,
+      &p_type,
+      &o_audio_track,
+      None,
+      None,
+      true,
+    );
+
+    if let Some(ref codec) = self.codec_rfc6381 {
+      add_codec(graph, &s_has_track, codec);
+    }
+    if self.bitrate_kbps > 0 {
+      add_link(
+        graph,
+        &s_has_track,
+        &p_bit_rate,
+        &format!("{}", self.bitrate_kbps * 1000),
+        None,
+        Some(format!("{}nonNegativeInteger", XML_NAMESPACE)),
+        false,
+      );
+    }
+    if self.sample_rate_hz > 0 {
+      add_link(
+        graph,
+        &s_has_track,
+        &p_sample_rate,
+        &format!("{}", self.sample_rate_hz),
+        None,
+        Some(format!("{}integer", XML_NAMESPACE)),
+        false,
+      );
+    }
+    if let Some(channels) = self.channels {
+      add_link(
+        graph,
+        &s_has_track,
+        &p_audio_channel_number,
+        &format!("{}", channels),
+        None,
+        Some(format!("{}nonNegativeInteger", XML_NAMESPACE)),
+        false,
+      );
+    }
+    if !self.lang.is_empty() {
+      let s_has_language = add_related_node(graph, &s_has_track, &p_has_language);
+      add_link(
+        graph,
+        &s_has_language,
+        &p_type,
+        &o_language,
+        None,
+        None,
+        true,
+      );
+      add_link(
+        graph,
+        &s_has_language,
+        &p_label,
+        &self.lang,
+        None,
+        None,
+        false,
+      );
+    }
   }
 }
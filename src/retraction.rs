@@ -0,0 +1,37 @@
+use crate::convert::ToRdf;
+use crate::namespaces::*;
+use crate::rdf_graph::add_triple;
+use rdf::{error::Error, graph::Graph};
+
+/// Graph identifying a video to withdraw, sent when the graph last published for the video is
+/// unknown (without graph cache nor SPARQL query endpoint, or never published).
+#[derive(Debug)]
+pub struct Retraction {
+  pub reference: String,
+}
+
+impl ToRdf for Retraction {
+  fn to_rdf(&self, graph: &mut Graph) -> Result<(), Error> {
+    let s_root = format!("http://resources.idfrancetv.fr/medias/{}", self.reference);
+    let p_type = format!("{}type", RDF_NAMESPACE);
+    let o_editorial_object = format!("{}EditorialObject", EBUCORE_NAMESPACE);
+
+    add_triple(graph, &s_root, &p_type, &o_editorial_object);
+    Ok(())
+  }
+}
+
+#[test]
+fn test_retraction_rdf() {
+  use crate::convert::convert_into_rdf;
+
+  let retraction = Retraction {
+    reference: "99787afd-ba2d-410f-b03e-66cf2efb3ed5".to_string(),
+  };
+
+  let rdf_triples = convert_into_rdf(&retraction, true).unwrap();
+  assert_eq!(
+    rdf_triples,
+    "<http://resources.idfrancetv.fr/medias/99787afd-ba2d-410f-b03e-66cf2efb3ed5> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.ebu.ch/metadata/ontologies/ebucore/ebucore#EditorialObject> .\n"
+  );
+}
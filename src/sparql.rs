@@ -0,0 +1,272 @@
+use mcai_worker_sdk::{
+  info,
+  job::{JobResult, JobStatus},
+  McaiChannel, MessageError,
+};
+use rdf::graph::Graph;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+
+use crate::convert::{read_n_triples, write_graph};
+use crate::http::{HttpClient, Idempotency};
+use crate::publisher::{Event, EventKind, Publisher};
+use crate::stop_signal::StopSignal;
+
+pub fn get_video_uri(reference: &str) -> String {
+  format!("http://resources.idfrancetv.fr/medias/{}", reference)
+}
+
+// Depth of the blank nodes matched below a video, beyond the ones of the video model
+const MAX_BLANK_NODE_DEPTH: usize = 5;
+
+/// Graph pattern matching the triples about a video and the blank nodes reachable from it
+///
+/// Only blank nodes are followed: the resources shared with other videos, like a programme, and
+/// their blank nodes are left out.
+pub fn get_video_graph_pattern(reference: &str) -> String {
+  let video_uri = get_video_uri(reference);
+  let mut pattern = format!(
+    "{{\n    <{video_uri}> ?p ?o .\n    BIND(<{video_uri}> AS ?s)\n  }}",
+    video_uri = video_uri
+  );
+
+  for depth in 1..=MAX_BLANK_NODE_DEPTH {
+    let mut subject = format!("<{}>", video_uri);
+    pattern.push_str(" UNION {\n");
+    for hop in 1..=depth {
+      let object = if hop == depth {
+        "?s".to_string()
+      } else {
+        format!("?b{}", hop)
+      };
+      pattern.push_str(&format!(
+        "    {} ?p{} {} .\n    FILTER(isBlank({}))\n",
+        subject, hop, object, object
+      ));
+      subject = object;
+    }
+    pattern.push_str("    ?s ?p ?o .\n  }");
+  }
+  pattern
+}
+
+pub fn get_construct_video_graph_query(reference: &str) -> String {
+  format!(
+    "CONSTRUCT {{ ?s ?p ?o }}\nWHERE {{\n  {}\n}}",
+    get_video_graph_pattern(reference)
+  )
+}
+
+/// SPARQL Update operation deleting the triples about a video and its blank node closure
+pub fn get_delete_video_graph_update(reference: &str) -> String {
+  format!(
+    "DELETE {{ ?s ?p ?o }}\nWHERE {{\n  {}\n}}",
+    get_video_graph_pattern(reference)
+  )
+}
+
+/// SPARQL Update operation inserting N-Triples
+pub fn get_insert_data_update(triples: &str) -> String {
+  format!("INSERT DATA {{\n{}}}", triples)
+}
+
+/// Retrieve the graph of a video from a SPARQL query endpoint
+pub fn fetch_video_graph(
+  http_client: &HttpClient,
+  endpoint: &str,
+  reference: &str,
+) -> Result<Graph, String> {
+  let query = get_construct_video_graph_query(reference);
+  let request = http_client
+    .get(endpoint)
+    .header(ACCEPT, "application/n-triples")
+    .query(&[("query", query)]);
+
+  let response = http_client.send(request, Idempotency::Idempotent)?;
+
+  let status = response.status();
+  if !status.is_success() {
+    return Err(format!("Bad SPARQL endpoint response status: {:?}", status));
+  }
+
+  let content = response.text().map_err(|e| e.to_string())?;
+  read_n_triples(&content).map_err(|e| format!("Invalid SPARQL endpoint response: {}", e))
+}
+
+#[derive(Debug)]
+pub(crate) struct SparqlUpdateConfig {
+  pub(crate) endpoint: String,
+  pub(crate) username: Option<String>,
+  pub(crate) password: Option<String>,
+}
+
+/// Publisher sending a SPARQL 1.1 Update request for each event, replacing the triples about a
+/// video in a single request
+pub(crate) struct SparqlUpdatePublisher {
+  pub(crate) http_client: HttpClient,
+  pub(crate) config: SparqlUpdateConfig,
+}
+
+impl Publisher for SparqlUpdatePublisher {
+  fn publishes_diff(&self) -> bool {
+    false
+  }
+
+  fn publish(
+    &self,
+    job_result: JobResult,
+    _channel: &Option<McaiChannel>,
+    event: &Event,
+    _stop_signal: &StopSignal,
+  ) -> mcai_worker_sdk::Result<JobResult> {
+    let processing_error = |message: &str| {
+      MessageError::ProcessingError(
+        job_result
+          .clone()
+          .with_status(JobStatus::Error)
+          .with_message(message),
+      )
+    };
+
+    let reference = event.reference.ok_or_else(|| {
+      processing_error("The SPARQL Update publisher needs the triples of a single reference")
+    })?;
+
+    // INSERT DATA only accepts triples, without the prefixes of Turtle
+    let triples = if event.n_triples {
+      event.rdf.to_string()
+    } else {
+      write_graph(event.graph, true).map_err(|error| processing_error(&error.to_string()))?
+    };
+
+    let (update, idempotency) = match event.kind {
+      EventKind::Insert => (get_insert_data_update(&triples), Idempotency::NonIdempotent),
+      EventKind::Replace => (
+        format!(
+          "{} ;\n{}",
+          get_delete_video_graph_update(reference),
+          get_insert_data_update(&triples)
+        ),
+        Idempotency::Idempotent,
+      ),
+      EventKind::Retract => (
+        get_delete_video_graph_update(reference),
+        Idempotency::Idempotent,
+      ),
+      EventKind::Delete => {
+        return Err(processing_error(
+          "The SPARQL Update publisher can not delete some triples of a video",
+        ))
+      }
+    };
+
+    let mut request = self
+      .http_client
+      .post(&self.config.endpoint)
+      .header(CONTENT_TYPE, "application/sparql-update")
+      .body(update);
+    if let Some(username) = &self.config.username {
+      request = request.basic_auth(username, self.config.password.as_ref());
+    }
+
+    info!(
+      "Update the triples of {} on {}",
+      reference, self.config.endpoint
+    );
+    let response = self
+      .http_client
+      .send(request, idempotency)
+      .map_err(|error| processing_error(&error))?;
+
+    let status = response.status();
+    if !status.is_success() {
+      let text = response.text().unwrap_or_default();
+      return Err(processing_error(&format!(
+        "Bad SPARQL Update endpoint response: {} {}",
+        status, text
+      )));
+    }
+
+    Ok(job_result)
+  }
+}
+
+#[test]
+fn test_sparql_update_publisher() {
+  use crate::http::{start_test_server, test_event, test_graph, RetryPolicy, TEST_RDF};
+
+  let (address, server) = start_test_server(2, 204);
+  let publisher = SparqlUpdatePublisher {
+    http_client: HttpClient::new(RetryPolicy::default()).unwrap(),
+    config: SparqlUpdateConfig {
+      endpoint: format!("{}/ds/update", address),
+      username: None,
+      password: None,
+    },
+  };
+
+  let graph = test_graph();
+  let mut event = test_event(&graph);
+  event.kind = EventKind::Replace;
+  let stop_signal = StopSignal::default();
+  publisher
+    .publish(JobResult::new(0), &None, &event, &stop_signal)
+    .unwrap();
+
+  event.kind = EventKind::Retract;
+  publisher
+    .publish(JobResult::new(0), &None, &event, &stop_signal)
+    .unwrap();
+
+  let requests = server.join().unwrap();
+  assert!(requests[0].starts_with("POST /ds/update HTTP/1.1\r\n"));
+  assert!(requests[0].contains("content-type: application/sparql-update\r\n"));
+  assert!(!requests[0].contains("authorization"));
+  assert!(requests[0].ends_with(&format!(
+    "{} ;\nINSERT DATA {{\n{}}}",
+    get_delete_video_graph_update("99787afd"),
+    TEST_RDF
+  )));
+  assert!(requests[1].ends_with(&get_delete_video_graph_update("99787afd")));
+}
+
+#[test]
+fn test_video_graph_pattern() {
+  let pattern = get_video_graph_pattern("a");
+
+  assert!(pattern.starts_with(
+    "{\n    <http://resources.idfrancetv.fr/medias/a> ?p ?o .\n    BIND(<http://resources.idfrancetv.fr/medias/a> AS ?s)\n  }"
+  ));
+  assert_eq!(pattern.matches("} UNION {").count(), MAX_BLANK_NODE_DEPTH);
+  assert!(pattern
+    .contains("<http://resources.idfrancetv.fr/medias/a> ?p1 ?b1 .\n    FILTER(isBlank(?b1))\n"));
+  assert!(pattern.contains("?b4 ?p5 ?s .\n    FILTER(isBlank(?s))\n    ?s ?p ?o .\n  }"));
+
+  // Each hop from the video is only followed when its object is a blank node
+  let lines: Vec<&str> = pattern.lines().map(str::trim).collect();
+  let hops: Vec<usize> = (0..lines.len())
+    .filter(|&index| {
+      let predicate = lines[index].split(' ').nth(1).unwrap_or_default();
+      predicate.starts_with("?p") && predicate != "?p"
+    })
+    .collect();
+  assert_eq!(hops.len(), (1..=MAX_BLANK_NODE_DEPTH).sum::<usize>());
+  for index in hops {
+    let object = lines[index].split(' ').nth(2).unwrap();
+    assert_eq!(lines[index + 1], format!("FILTER(isBlank({}))", object));
+  }
+}
+
+#[test]
+fn test_delete_video_graph_update() {
+  let update = get_delete_video_graph_update("a");
+
+  assert_eq!(
+    update,
+    format!(
+      "DELETE {{ ?s ?p ?o }}\nWHERE {{\n  {}\n}}",
+      get_video_graph_pattern("a")
+    )
+  );
+  assert!(update.contains("BIND(<http://resources.idfrancetv.fr/medias/a> AS ?s)"));
+  assert!(update.contains("FILTER(isBlank(?b1))"));
+}
@@ -0,0 +1,100 @@
+use std::process;
+use std::sync::{
+  atomic::{AtomicBool, Ordering},
+  Arc,
+};
+use std::thread;
+use std::time::Duration;
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+/// Stop request received by the worker, checked by long running steps of a job.
+///
+/// It is raised by `SIGTERM` or `SIGINT`: when no job is running, or when the signal is
+/// received twice, the worker exits immediately. Otherwise the running job ends early, no other
+/// job is started once it returns, and the worker exits when it is idle.
+#[derive(Clone, Debug)]
+pub struct StopSignal {
+  raised: Arc<AtomicBool>,
+  idle: Arc<AtomicBool>,
+}
+
+impl Default for StopSignal {
+  fn default() -> Self {
+    StopSignal {
+      raised: Arc::new(AtomicBool::new(false)),
+      idle: Arc::new(AtomicBool::new(true)),
+    }
+  }
+}
+
+impl StopSignal {
+  pub fn register_os_signals(&self) -> std::io::Result<()> {
+    for signal in &[SIGTERM, SIGINT] {
+      signal_hook::flag::register_conditional_shutdown(*signal, 1, self.raised.clone())?;
+      signal_hook::flag::register_conditional_shutdown(*signal, 1, self.idle.clone())?;
+      signal_hook::flag::register(*signal, self.raised.clone())?;
+    }
+    Ok(())
+  }
+
+  pub fn is_raised(&self) -> bool {
+    self.raised.load(Ordering::SeqCst)
+  }
+
+  #[cfg(test)]
+  pub(crate) fn raise(&self) {
+    self.raised.store(true, Ordering::SeqCst);
+  }
+
+  /// Exit the worker once a stop is requested and no job is running anymore, from a thread
+  /// checking it every second: the SDK is left two checks to acknowledge the last job
+  pub fn exit_when_stopped(&self) {
+    let stop_signal = self.clone();
+    thread::spawn(move || {
+      let mut idle_checks = 0;
+      while idle_checks < 2 {
+        thread::sleep(Duration::from_secs(1));
+        if stop_signal.is_raised() && stop_signal.idle.load(Ordering::SeqCst) {
+          idle_checks += 1;
+        } else {
+          idle_checks = 0;
+        }
+      }
+      eprintln!("Stop requested, exit");
+      process::exit(0);
+    });
+  }
+
+  /// Mark a job as running until the returned guard is dropped, or `None` when a stop has been
+  /// requested and the job must not be started
+  pub fn running_job(&self) -> Option<RunningJob> {
+    if self.is_raised() {
+      return None;
+    }
+    self.idle.store(false, Ordering::SeqCst);
+    Some(RunningJob {
+      idle: self.idle.clone(),
+    })
+  }
+}
+
+pub struct RunningJob {
+  idle: Arc<AtomicBool>,
+}
+
+impl Drop for RunningJob {
+  fn drop(&mut self) {
+    self.idle.store(true, Ordering::SeqCst);
+  }
+}
+
+#[test]
+fn test_stop_signal_kept_after_job() {
+  let stop_signal = StopSignal::default();
+  drop(stop_signal.running_job().unwrap());
+
+  stop_signal.raised.store(true, Ordering::SeqCst);
+  assert!(stop_signal.running_job().is_none());
+  assert!(stop_signal.is_raised());
+}
@@ -0,0 +1,126 @@
+use mcai_worker_sdk::warn;
+use std::{env, fs};
+
+/// Environment variable pointing to a JSON file with additional storage profiles
+pub const STORAGE_PROFILES_PATH_ENV: &str = "STORAGE_PROFILES_PATH";
+
+/// Storage whose profile is used for the storages without one
+pub const DEFAULT_STORAGE: &str = "akamai-video-prod";
+
+/// Storage where delivered files are published, with the URL prefix to reach them
+#[derive(Clone, Debug, Deserialize)]
+pub struct StorageProfile {
+  pub storage: String,
+  pub url_prefix: String,
+}
+
+/// Known storage profiles, looked up by storage name
+#[derive(Clone, Debug)]
+pub struct StorageProfiles {
+  profiles: Vec<StorageProfile>,
+}
+
+impl Default for StorageProfiles {
+  fn default() -> Self {
+    let profiles = vec![
+      StorageProfile {
+        storage: DEFAULT_STORAGE.to_string(),
+        url_prefix: "http://videos-pmd.francetv.fr/innovation/SubTil/".to_string(),
+      },
+      StorageProfile {
+        storage: "ftv.video.media-io.com".to_string(),
+        url_prefix: "https://ftv.video.media-io.com/".to_string(),
+      },
+    ];
+
+    StorageProfiles { profiles }
+  }
+}
+
+impl StorageProfiles {
+  /// Built-in profiles, extended with the file set in `STORAGE_PROFILES_PATH` if any
+  pub fn from_env() -> Result<Self, String> {
+    let mut profiles = StorageProfiles::default();
+    if let Ok(path) = env::var(STORAGE_PROFILES_PATH_ENV) {
+      profiles.load(&path)?;
+    }
+    Ok(profiles)
+  }
+
+  /// Add the profiles of a JSON file, they override built-in profiles with the same storage
+  pub fn load(&mut self, path: &str) -> Result<(), String> {
+    let content = fs::read_to_string(path)
+      .map_err(|error| format!("Unable to read storage profiles {}: {}", path, error))?;
+    let profiles: Vec<StorageProfile> = serde_json::from_str(&content)
+      .map_err(|error| format!("Invalid storage profiles {}: {}", path, error))?;
+
+    self.extend(profiles);
+    Ok(())
+  }
+
+  pub fn extend(&mut self, profiles: Vec<StorageProfile>) {
+    for profile in profiles {
+      self
+        .profiles
+        .retain(|existing| existing.storage != profile.storage);
+      self.profiles.push(profile);
+    }
+  }
+
+  pub fn find(&self, storage: &str) -> Option<&StorageProfile> {
+    self
+      .profiles
+      .iter()
+      .find(|profile| profile.storage == storage)
+  }
+
+  /// Profile of the storage, using `url_prefix` when set instead of the configured one, and the
+  /// URL prefix of the default storage for an unknown storage
+  pub fn resolve(&self, storage: &str, url_prefix: Option<String>) -> StorageProfile {
+    let url_prefix = url_prefix.unwrap_or_else(|| {
+      let profile = self.find(storage).or_else(|| {
+        warn!(
+          "No profile for storage {}, use the one of {}",
+          storage, DEFAULT_STORAGE
+        );
+        self.find(DEFAULT_STORAGE)
+      });
+      profile
+        .map(|profile| profile.url_prefix.clone())
+        .unwrap_or_default()
+    });
+
+    StorageProfile {
+      storage: storage.to_string(),
+      url_prefix,
+    }
+  }
+}
+
+#[test]
+fn test_resolve_storage_profile() {
+  let mut profiles = StorageProfiles::default();
+  profiles.extend(vec![StorageProfile {
+    storage: "cdn".to_string(),
+    url_prefix: "https://cdn.example.com/".to_string(),
+  }]);
+
+  let profile = profiles.resolve("akamai-video-prod", None);
+  assert_eq!(
+    profile.url_prefix,
+    "http://videos-pmd.francetv.fr/innovation/SubTil/"
+  );
+
+  let profile = profiles.resolve("cdn", None);
+  assert_eq!(profile.url_prefix, "https://cdn.example.com/");
+
+  let profile = profiles.resolve("cdn", Some("https://other.example.com/".to_string()));
+  assert_eq!(profile.url_prefix, "https://other.example.com/");
+
+  let profile = profiles.resolve("unknown", None);
+  assert_eq!(profile.storage, "unknown");
+  assert_eq!(
+    profile.url_prefix,
+    "http://videos-pmd.francetv.fr/innovation/SubTil/"
+  );
+}
@@ -0,0 +1,171 @@
+use rdf::{error::Error, graph::Graph, uri::Uri};
+
+use crate::convert::ToRdf;
+use crate::inspection::{format_duration, Cue, TimedText};
+use crate::namespaces::*;
+use crate::rdf_graph::{add_link, add_related_node};
+
+/// Timed text of the subtitles of a video, published as parts of its editorial object
+#[derive(Debug)]
+pub struct Transcript {
+  pub reference: String,
+  pub texts: Vec<TimedText>,
+}
+
+impl ToRdf for Transcript {
+  fn to_rdf(&self, graph: &mut Graph) -> Result<(), Error> {
+    let s_root = format!("http://resources.idfrancetv.fr/medias/{}", self.reference);
+    let root_node = graph.create_uri_node(&Uri::new(s_root));
+
+    let p_end = format!("{}endNormalPlayTime", EBUCORE_NAMESPACE);
+    let p_has_part = format!("{}hasPart", EBUCORE_NAMESPACE);
+    let p_has_related_text_line = format!("{}hasRelatedTextLine", EBUCORE_NAMESPACE);
+    let p_part_number = format!("{}partNumber", EBUCORE_NAMESPACE);
+    let p_start = format!("{}startNormalPlayTime", EBUCORE_NAMESPACE);
+    let p_text_line_content = format!("{}textLineContent", EBUCORE_NAMESPACE);
+    let p_type = format!("{}type", RDF_NAMESPACE);
+
+    let o_part = format!("{}Part", EBUCORE_NAMESPACE);
+    let o_text_line = format!("{}TextLine", EBUCORE_NAMESPACE);
+
+    for text in &self.texts {
+      for (index, cue) in text.cues.iter().enumerate() {
+        let s_has_part = add_related_node(graph, &root_node, &p_has_part);
+        add_link(graph, &s_has_part, &p_type, &o_part, None, None, true);
+        add_link(
+          graph,
+          &s_has_part,
+          &p_part_number,
+          &format!("{}", index + 1),
+          None,
+          Some(format!("{}nonNegativeInteger", XML_NAMESPACE)),
+          false,
+        );
+        add_link(
+          graph,
+          &s_has_part,
+          &p_start,
+          &format_duration(cue.begin),
+          None,
+          Some(format!("{}duration", XML_NAMESPACE)),
+          false,
+        );
+        add_link(
+          graph,
+          &s_has_part,
+          &p_end,
+          &format_duration(cue.end),
+          None,
+          Some(format!("{}duration", XML_NAMESPACE)),
+          false,
+        );
+
+        let s_has_related_text_line =
+          add_related_node(graph, &s_has_part, &p_has_related_text_line);
+        add_link(
+          graph,
+          &s_has_related_text_line,
+          &p_type,
+          &o_text_line,
+          None,
+          None,
+          true,
+        );
+        add_link(
+          graph,
+          &s_has_related_text_line,
+          &p_text_line_content,
+          &cue.text,
+          text.lang.as_deref(),
+          None,
+          false,
+        );
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Merge consecutive cues until the end of a sentence
+pub fn group_sentences(cues: Vec<Cue>) -> Vec<Cue> {
+  let mut sentences: Vec<Cue> = vec![];
+  let mut open_sentence = false;
+
+  for cue in cues {
+    let ends_sentence = cue.text.trim_end().ends_with(['.', '!', '?', '…']);
+
+    match sentences.last_mut() {
+      Some(sentence) if open_sentence => {
+        sentence.end = cue.end;
+        sentence.text = format!("{} {}", sentence.text, cue.text.replace('\n', " "));
+      }
+      _ => sentences.push(Cue {
+        text: cue.text.replace('\n', " "),
+        ..cue
+      }),
+    }
+    open_sentence = !ends_sentence;
+  }
+
+  sentences
+}
+
+#[test]
+fn test_group_sentences() {
+  let cues = vec![
+    Cue {
+      begin: 1.0,
+      end: 2.0,
+      text: "Bonjour à tous,".to_string(),
+    },
+    Cue {
+      begin: 2.5,
+      end: 4.0,
+      text: "et bienvenue\ndans l'émission.".to_string(),
+    },
+    Cue {
+      begin: 5.0,
+      end: 6.0,
+      text: "Au revoir !".to_string(),
+    },
+  ];
+
+  assert_eq!(
+    group_sentences(cues),
+    vec![
+      Cue {
+        begin: 1.0,
+        end: 4.0,
+        text: "Bonjour à tous, et bienvenue dans l'émission.".to_string(),
+      },
+      Cue {
+        begin: 5.0,
+        end: 6.0,
+        text: "Au revoir !".to_string(),
+      },
+    ]
+  );
+}
+
+#[test]
+fn test_transcript_rdf() {
+  use crate::convert::convert_into_rdf;
+
+  let transcript = Transcript {
+    reference: "99787afd-ba2d-410f-b03e-66cf2efb3ed5".to_string(),
+    texts: vec![TimedText {
+      lang: Some("fr".to_string()),
+      cues: vec![Cue {
+        begin: 1.5,
+        end: 3.0,
+        text: "Bonjour".to_string(),
+      }],
+      sdh: false,
+    }],
+  };
+
+  let rdf_triples = convert_into_rdf(&transcript, true).unwrap();
+  assert!(rdf_triples.contains("<http://resources.idfrancetv.fr/medias/99787afd-ba2d-410f-b03e-66cf2efb3ed5> <http://www.ebu.ch/metadata/ontologies/ebucore/ebucore#hasPart> _:auto0 .\n"));
+  assert!(rdf_triples.contains("_:auto0 <http://www.ebu.ch/metadata/ontologies/ebucore/ebucore#startNormalPlayTime> \"PT1.5S\"^^<http://www.w3.org/2001/XMLSchema#duration> .\n"));
+  assert!(rdf_triples.contains("_:auto1 <http://www.ebu.ch/metadata/ontologies/ebucore/ebucore#textLineContent> \"Bonjour\"@fr .\n"));
+}
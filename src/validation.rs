@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use rdf::{graph::Graph, node::Node};
+
+use crate::namespaces::*;
+use crate::rdf_graph::get_node_value;
+
+/// Properties required on the nodes of a class, like a minimal SHACL node shape
+struct Shape {
+  class: &'static str,
+  properties: &'static [(&'static str, &'static str)],
+}
+
+const SHAPES: &[Shape] = &[
+  Shape {
+    class: "Codec",
+    properties: &[(EBUCORE_NAMESPACE, "codecId")],
+  },
+  Shape {
+    class: "Group",
+    properties: &[(EBUCORE_NAMESPACE, "groupId")],
+  },
+  Shape {
+    class: "Identifier",
+    properties: &[
+      (EBUCORE_NAMESPACE, "identifierValue"),
+      (EBUCORE_NAMESPACE, "hasIdentifierType"),
+    ],
+  },
+  Shape {
+    class: "Language",
+    properties: &[(SKOS_NAMESPACE, "label")],
+  },
+  Shape {
+    class: "MediaResource",
+    properties: &[
+      (EBUCORE_NAMESPACE, "resourceId"),
+      (EBUCORE_NAMESPACE, "hasFormat"),
+    ],
+  },
+  Shape {
+    class: "Organisation",
+    properties: &[(EBUCORE_NAMESPACE, "organisationName")],
+  },
+  Shape {
+    class: "Part",
+    properties: &[
+      (EBUCORE_NAMESPACE, "startNormalPlayTime"),
+      (EBUCORE_NAMESPACE, "endNormalPlayTime"),
+    ],
+  },
+  Shape {
+    class: "Picture",
+    properties: &[
+      (EBUCORE_NAMESPACE, "resourceId"),
+      (EBUCORE_NAMESPACE, "hasFormat"),
+    ],
+  },
+  Shape {
+    class: "Tag",
+    properties: &[(SKOS_NAMESPACE, "prefLabel")],
+  },
+];
+
+/// Constraint of the graph not satisfied by a node
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+  pub focus_node: String,
+  pub message: String,
+}
+
+/// Check the EBUCore classes against their shapes, and the typed literals against their datatype
+pub fn validate_graph(graph: &Graph) -> Vec<Violation> {
+  let p_type = format!("{}type", RDF_NAMESPACE);
+
+  let mut predicates: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+  let mut classes: Vec<(String, String)> = vec![];
+  let mut violations = vec![];
+
+  for triple in graph.triples_iter() {
+    let subject = get_node_value(triple.subject());
+    let predicate = get_node_value(triple.predicate());
+
+    if predicate == p_type {
+      classes.push((subject.clone(), get_node_value(triple.object())));
+    }
+    if let Node::LiteralNode {
+      literal,
+      data_type: Some(data_type),
+      ..
+    } = triple.object()
+    {
+      if !is_valid_literal(literal, data_type.to_string()) {
+        violations.push(Violation {
+          focus_node: subject.clone(),
+          message: format!(
+            "Invalid {} value of {}: {}",
+            data_type.to_string(),
+            predicate,
+            literal
+          ),
+        });
+      }
+    }
+    predicates.entry(subject).or_default().insert(predicate);
+  }
+
+  for (subject, class) in classes {
+    let shape = SHAPES
+      .iter()
+      .find(|shape| format!("{}{}", EBUCORE_NAMESPACE, shape.class) == class);
+    let shape = match shape {
+      Some(shape) => shape,
+      None => continue,
+    };
+
+    for (namespace, property) in shape.properties {
+      let property = format!("{}{}", namespace, property);
+      if !predicates[&subject].contains(&property) {
+        violations.push(Violation {
+          focus_node: subject.clone(),
+          message: format!("Missing {} on {}", property, class),
+        });
+      }
+    }
+  }
+
+  violations
+}
+
+fn is_valid_literal(literal: &str, data_type: &str) -> bool {
+  match data_type.strip_prefix(XML_NAMESPACE) {
+    Some("boolean") => literal == "true" || literal == "false",
+    Some("integer") => literal.parse::<i64>().is_ok(),
+    Some("nonNegativeInteger") => literal.parse::<u64>().is_ok(),
+    Some("duration") => literal.starts_with('P') || literal.starts_with("-P"),
+    _ => true,
+  }
+}
+
+#[test]
+fn test_validate_graph() {
+  use crate::convert::read_n_triples;
+  use std::fs;
+
+  let content = fs::read_to_string("tests/triples.nt").unwrap();
+  let graph = read_n_triples(&content).unwrap();
+  assert_eq!(validate_graph(&graph), vec![]);
+
+  let graph = read_n_triples(
+    r#"_:b0 <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.ebu.ch/metadata/ontologies/ebucore/ebucore#Codec> .
+_:b1 <http://www.ebu.ch/metadata/ontologies/ebucore/ebucore#bitRate> "fast"^^<http://www.w3.org/2001/XMLSchema#nonNegativeInteger> .
+"#,
+  )
+  .unwrap();
+  let violations = validate_graph(&graph);
+  assert_eq!(violations.len(), 2);
+  assert_eq!(
+    violations[1].message,
+    "Missing http://www.ebu.ch/metadata/ontologies/ebucore/ebucore#codecId on http://www.ebu.ch/metadata/ontologies/ebucore/ebucore#Codec"
+  );
+}
@@ -1,3 +1,5 @@
+// Not every field of the video factory API is mapped to RDF
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct Category {
   pub id: String,
@@ -1,5 +1,7 @@
 use crate::video_model::region::Region;
 
+// Not every field of the video factory API is mapped to RDF
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct Channel {
   pub id: String,
@@ -7,9 +7,11 @@ use crate::video_model::{
 };
 use rdf::{error::Error, graph::Graph};
 
-use crate::rdf_graph::{add_link, add_related_node, add_triple, insert_identifier};
+use crate::rdf_graph::{add_link, add_node_link, add_related_node, add_triple, insert_identifier};
 use crate::resource_model::Resources;
 
+// Not every field of the video factory API is mapped to RDF
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct Metadata {
   created_at: String,
@@ -187,7 +189,7 @@ impl ToRdf for Metadata {
         graph,
         &subject,
         &p_original_title,
-        &original_title,
+        original_title,
         Some("fr"),
         None,
         false,
@@ -199,7 +201,7 @@ impl ToRdf for Metadata {
         graph,
         &subject,
         &p_alternative_title,
-        &additional_title,
+        additional_title,
         Some("fr"),
         None,
         false,
@@ -211,7 +213,7 @@ impl ToRdf for Metadata {
         graph,
         &subject,
         &p_synopsis,
-        &description,
+        description,
         Some("fr"),
         None,
         false,
@@ -223,7 +225,7 @@ impl ToRdf for Metadata {
         graph,
         &subject,
         &p_abstract,
-        &short_description,
+        short_description,
         Some("fr"),
         None,
         false,
@@ -235,7 +237,7 @@ impl ToRdf for Metadata {
         graph,
         &subject,
         &p_duration,
-        &duration,
+        duration,
         None,
         Some(format!("{}duration", XML_NAMESPACE)),
         false,
@@ -280,7 +282,7 @@ impl ToRdf for Metadata {
         graph,
         &subject,
         &p_date_broadcast,
-        &broadcasted_at,
+        broadcasted_at,
         None,
         Some(format!("{}dateTime", XML_NAMESPACE)),
         false,
@@ -293,7 +295,7 @@ impl ToRdf for Metadata {
         graph,
         &subject,
         &p_has_creator,
-        &created_by,
+        created_by,
         None,
         None,
         false,
@@ -329,7 +331,7 @@ impl ToRdf for Metadata {
         graph,
         &s_has_owner,
         &p_organisation_name,
-        &copyright,
+        copyright,
         None,
         None,
         false,
@@ -344,7 +346,7 @@ impl ToRdf for Metadata {
       &format!("urn::uuid:{}", self.id),
     );
     if let Some(ref oscar_id) = self.oscar_id {
-      insert_identifier(graph, &subject, "Oscar_ID", &oscar_id);
+      insert_identifier(graph, &subject, "Oscar_ID", oscar_id);
     }
 
     if let Some(ref plurimedia_broadcast_id) = self.plurimedia_broadcast_id {
@@ -375,7 +377,7 @@ impl ToRdf for Metadata {
     }
 
     if let Some(ref ftvcut_id) = self.ftvcut_id {
-      insert_identifier(graph, &subject, "FTVCUT", &ftvcut_id);
+      insert_identifier(graph, &subject, "FTVCUT", ftvcut_id);
     }
 
     // episode
@@ -417,7 +419,7 @@ impl ToRdf for Metadata {
           graph,
           &s_group,
           &p_group_description,
-          &description,
+          description,
           None,
           None,
           false,
@@ -463,6 +465,7 @@ impl ToRdf for Metadata {
     }
 
     // text_tracks
+    let mut text_lines = vec![];
     for text_track in &self.text_tracks {
       let s_has_related_text_line = add_related_node(graph, &subject, &p_has_related_text_line);
       add_link(
@@ -483,6 +486,7 @@ impl ToRdf for Metadata {
         None,
         true,
       );
+      text_lines.push((text_track, s_has_related_text_line));
     }
 
     // publication event
@@ -541,7 +545,7 @@ impl ToRdf for Metadata {
         graph,
         &s_publication_event,
         &p_publication_start_date_time,
-        &expected_at,
+        expected_at,
         None,
         Some(format!("{}dateTime", XML_NAMESPACE)),
         false,
@@ -552,7 +556,7 @@ impl ToRdf for Metadata {
         graph,
         &s_publication_event,
         &p_duration_normal_play_time,
-        &duration,
+        duration,
         None,
         Some(format!("{}duration", XML_NAMESPACE)),
         false,
@@ -627,7 +631,7 @@ impl ToRdf for Metadata {
         graph,
         &s_publication_event,
         &p_date_broadcast,
-        &broadcasted_at,
+        broadcasted_at,
         None,
         Some(format!("{}dateTime", XML_NAMESPACE)),
         false,
@@ -692,7 +696,7 @@ impl ToRdf for Metadata {
           graph,
           &s_is_agent,
           &p_given_name,
-          &first_name,
+          first_name,
           None,
           None,
           false,
@@ -723,7 +727,7 @@ impl ToRdf for Metadata {
           graph,
           &s_is_character,
           &p_character_name,
-          &character,
+          character,
           None,
           None,
           false,
@@ -731,6 +735,23 @@ impl ToRdf for Metadata {
       }
     }
 
-    self.resources.to_rdf(graph)
+    // subtitles files are linked to the text line of their language
+    let p_has_related_resource = format!("{}hasRelatedResource", EBUCORE_NAMESPACE);
+    for resource in &self.resources.items {
+      let s_resource = resource.add_to_graph(graph)?;
+      if resource.format.kind != "caption" {
+        continue;
+      }
+
+      let text_line = resource.lang.as_ref().and_then(|lang| {
+        text_lines
+          .iter()
+          .find(|(text_track, _)| text_track.matches_language(lang))
+      });
+      if let Some((_, s_text_line)) = text_line {
+        add_node_link(graph, s_text_line, &p_has_related_resource, &s_resource);
+      }
+    }
+    Ok(())
   }
 }
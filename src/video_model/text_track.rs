@@ -1,6 +1,57 @@
+// Not every field of the video factory API is mapped to RDF
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct TextTrack {
   pub id: String,
   label: String,
   code: String,
 }
+
+impl TextTrack {
+  /// Whether the track is in the language `lang`, given as an ISO 639-1 or ISO 639-2 code,
+  /// possibly with a region (`fr`, `fra`, `fre`, `fr-FR`)
+  pub fn matches_language(&self, lang: &str) -> bool {
+    to_iso_639_2(&self.code) == to_iso_639_2(lang)
+  }
+}
+
+/// ISO 639-2/T code of a language code
+fn to_iso_639_2(lang: &str) -> String {
+  let lang = lang
+    .split(['-', '_'])
+    .next()
+    .unwrap_or_default()
+    .to_lowercase();
+
+  let code = match lang.as_str() {
+    "ar" => "ara",
+    "de" | "ger" => "deu",
+    "en" => "eng",
+    "es" => "spa",
+    "fr" | "fre" => "fra",
+    "it" => "ita",
+    "ja" => "jpn",
+    "nl" | "dut" => "nld",
+    "pl" => "pol",
+    "pt" => "por",
+    "ru" => "rus",
+    "zh" | "chi" => "zho",
+    _ => return lang,
+  };
+  code.to_string()
+}
+
+#[test]
+fn test_matches_language() {
+  let text_track = TextTrack {
+    id: "francais".to_string(),
+    label: "Français".to_string(),
+    code: "FRA".to_string(),
+  };
+
+  assert!(text_track.matches_language("fr"));
+  assert!(text_track.matches_language("fr-FR"));
+  assert!(text_track.matches_language("fre"));
+  assert!(text_track.matches_language("fra"));
+  assert!(!text_track.matches_language("en"));
+}
@@ -0,0 +1,332 @@
+use std::collections::BTreeMap;
+
+use mcai_worker_sdk::{
+  info,
+  job::{JobResult, JobStatus},
+  McaiChannel, MessageError, Parameter, ParameterValue, Result,
+};
+use reqwest::{
+  header::{CONTENT_TYPE, LOCATION},
+  Url,
+};
+
+use crate::http::{HttpClient, Idempotency};
+use crate::perfect_memory::{wait_for_request, PmConfig, PolledRequest};
+use crate::publisher::{Event, Publisher};
+use crate::stop_signal::StopSignal;
+
+/// Body posted to a webhook for each event, when no template is set
+#[derive(Debug, Serialize)]
+struct WebhookBody<'a> {
+  job_id: u64,
+  reference: Option<&'a str>,
+  event: &'a str,
+  format: &'a str,
+  rdf: &'a str,
+}
+
+#[derive(Debug)]
+pub(crate) struct WebhookConfig {
+  pub(crate) url: String,
+  pub(crate) headers: BTreeMap<String, String>,
+  pub(crate) body_template: Option<String>,
+  pub(crate) content_type: Option<String>,
+  pub(crate) expected_statuses: Option<Vec<u16>>,
+}
+
+/// Publisher posting the RDF of each event to a URL.
+///
+/// When the response has a `Location` header, the request is polled like a Perfect Memory one.
+pub(crate) struct WebhookPublisher {
+  pub(crate) http_client: HttpClient,
+  pub(crate) config: WebhookConfig,
+  /// Polling of the requests
+  pub(crate) poll_config: PmConfig,
+}
+
+impl Publisher for WebhookPublisher {
+  fn publish(
+    &self,
+    job_result: JobResult,
+    channel: &Option<McaiChannel>,
+    event: &Event,
+    stop_signal: &StopSignal,
+  ) -> Result<JobResult> {
+    let processing_error = |message: &str| {
+      MessageError::ProcessingError(
+        job_result
+          .clone()
+          .with_status(JobStatus::Error)
+          .with_message(message),
+      )
+    };
+
+    let format = if event.n_triples {
+      "ntriples"
+    } else {
+      "turtle"
+    };
+    let content_type = self
+      .config
+      .content_type
+      .as_deref()
+      .unwrap_or("application/json");
+
+    let body = match self.config.body_template {
+      Some(ref body_template) => {
+        let values = [
+          ("rdf", event.rdf.to_string()),
+          ("rdf_base64", base64::encode(event.rdf)),
+          ("reference", event.reference.unwrap_or_default().to_string()),
+          ("job_id", job_result.get_job_id().to_string()),
+          ("event", event.name.to_string()),
+          ("format", format.to_string()),
+        ];
+        render_template(body_template, &values, content_type.contains("json"))
+      }
+      None => serde_json::to_string(&WebhookBody {
+        job_id: job_result.get_job_id(),
+        reference: event.reference,
+        event: event.name,
+        format,
+        rdf: event.rdf,
+      })
+      .map_err(|error| processing_error(&error.to_string()))?,
+    };
+
+    let mut request = self
+      .http_client
+      .post(&self.config.url)
+      .header(CONTENT_TYPE, content_type)
+      .body(body);
+    for (name, value) in &self.config.headers {
+      request = request.header(name.as_str(), value.as_str());
+    }
+
+    info!("Post {} to {}", event.name, self.config.url);
+    let response = self
+      .http_client
+      .send(request, Idempotency::NonIdempotent)
+      .map_err(|error| processing_error(&error))?;
+
+    let status = response.status();
+    let expected = match self.config.expected_statuses {
+      Some(ref expected_statuses) => expected_statuses.contains(&status.as_u16()),
+      None => status.is_success(),
+    };
+    if !expected {
+      let text = response.text().unwrap_or_default();
+      return Err(processing_error(&format!(
+        "Bad webhook response: {} {}",
+        status, text
+      )));
+    }
+
+    let location = match response.headers().get(LOCATION) {
+      Some(location) => location,
+      None => return Ok(job_result),
+    };
+    // The location can be relative to the webhook URL
+    let url = Url::parse(&self.config.url)
+      .map_err(|error| processing_error(&format!("Invalid webhook URL: {}", error)))?;
+    let location = location
+      .to_str()
+      .ok()
+      .and_then(|location| url.join(location).ok())
+      .ok_or_else(|| processing_error("Invalid webhook response location"))?;
+    // The headers, credentials among them, are only sent back to the origin of the webhook
+    let no_headers = BTreeMap::new();
+    let headers = if location.origin() == url.origin() {
+      &self.config.headers
+    } else {
+      &no_headers
+    };
+    let location = location.to_string();
+
+    let job_result = job_result.with_parameters(&mut vec![Parameter {
+      id: "webhook_location".to_string(),
+      kind: String::get_type_as_string(),
+      store: None,
+      value: Some(location.as_str().into()),
+      default: None,
+    }]);
+
+    if !self.poll_config.wait_for_completion {
+      info!(
+        "Webhook request created at {}, do not wait for its completion",
+        location
+      );
+      return Ok(job_result);
+    }
+
+    let request = PolledRequest {
+      service: "webhook",
+      location: &location,
+      headers,
+      response_parameter: "webhook_response",
+      progression: event.progression,
+      // The disruption is a Perfect Memory status, not a webhook one
+      disrupt_on_stop: false,
+    };
+    wait_for_request(
+      job_result,
+      channel,
+      &self.http_client,
+      &self.poll_config,
+      &request,
+      stop_signal,
+    )
+  }
+}
+
+/// Replace the `{{name}}` placeholders of a template, escaping the values for a JSON string
+/// when `json` is set.
+///
+/// The template is read once, so placeholders written in the values are kept as they are.
+fn render_template(template: &str, values: &[(&str, String)], json: bool) -> String {
+  let mut body = String::with_capacity(template.len());
+  let mut rest = template;
+  while let Some(start) = rest.find("{{") {
+    body.push_str(&rest[..start]);
+    rest = &rest[start..];
+
+    let value = rest.find("}}").and_then(|end| {
+      let (_, value) = values.iter().find(|(name, _)| *name == &rest[2..end])?;
+      Some((end, value))
+    });
+    match value {
+      Some((end, value)) if json => {
+        let escaped = serde_json::Value::String(value.clone()).to_string();
+        body.push_str(&escaped[1..escaped.len() - 1]);
+        rest = &rest[end + 2..];
+      }
+      Some((end, value)) => {
+        body.push_str(value);
+        rest = &rest[end + 2..];
+      }
+      None => {
+        body.push_str("{{");
+        rest = &rest[2..];
+      }
+    }
+  }
+  body.push_str(rest);
+  body
+}
+
+#[test]
+fn test_render_template() {
+  let values = [
+    ("rdf", "<a> <b> \"c\" .\n".to_string()),
+    ("reference", "99787afd".to_string()),
+  ];
+
+  assert_eq!(
+    render_template(
+      r#"{"reference": "{{reference}}", "rdf": "{{rdf}}"}"#,
+      &values,
+      true
+    ),
+    r#"{"reference": "99787afd", "rdf": "<a> <b> \"c\" .\n"}"#
+  );
+  assert_eq!(
+    render_template("{{reference}}: {{rdf}}", &values, false),
+    "99787afd: <a> <b> \"c\" .\n"
+  );
+
+  // Placeholders of the values and unknown placeholders are not replaced
+  let values = [
+    ("rdf", "<a> <b> \"{{reference}}\" .\n".to_string()),
+    ("reference", "99787afd".to_string()),
+  ];
+  assert_eq!(
+    render_template("{{rdf}}{{unknown}} {{reference}}", &values, false),
+    "<a> <b> \"{{reference}}\" .\n{{unknown}} 99787afd"
+  );
+}
+
+#[test]
+fn test_webhook_publisher_polling() {
+  use crate::http::{
+    start_test_server_with_responses, test_event, test_graph, RetryPolicy, TEST_RDF,
+  };
+  use crate::RdfWorkerParameters;
+
+  let (address, server) = start_test_server_with_responses(vec![
+    "HTTP/1.1 202 Accepted\r\nLocation: /requests/1\r\n".to_string(),
+    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"status\": 200}".to_string(),
+  ]);
+  let publisher = WebhookPublisher {
+    http_client: HttpClient::new(RetryPolicy::default()).unwrap(),
+    config: WebhookConfig {
+      url: format!("{}/ingest", address),
+      headers: vec![("X-Api-Key".to_string(), "secret".to_string())]
+        .into_iter()
+        .collect(),
+      body_template: Some(
+        r#"{"name": "{{event}}", "inputs": {"infos_graph": {"value": "{{rdf_base64}}", "type": "binary"}}}"#
+          .to_string(),
+      ),
+      content_type: None,
+      expected_statuses: Some(vec![202]),
+    },
+    poll_config: PmConfig::from(RdfWorkerParameters::default()),
+  };
+
+  let graph = test_graph();
+  let event = test_event(&graph);
+  let job_result = publisher
+    .publish(JobResult::new(0), &None, &event, &StopSignal::default())
+    .unwrap();
+  assert!(job_result
+    .get_parameters()
+    .iter()
+    .any(|parameter| parameter.id == "webhook_response"));
+
+  let requests = server.join().unwrap();
+  assert!(requests[0].starts_with("POST /ingest HTTP/1.1\r\n"));
+  assert!(requests[0].ends_with(&format!(
+    r#"{{"name": "push_rdf_infos", "inputs": {{"infos_graph": {{"value": "{}", "type": "binary"}}}}}}"#,
+    base64::encode(TEST_RDF)
+  )));
+  assert!(requests[1].starts_with("GET /requests/1 HTTP/1.1\r\n"));
+  assert!(requests[1].contains("x-api-key: secret\r\n"));
+}
+
+#[test]
+fn test_webhook_publisher_other_origin() {
+  use crate::http::{start_test_server_with_responses, test_event, test_graph, RetryPolicy};
+  use crate::RdfWorkerParameters;
+
+  let (location_address, location_server) = start_test_server_with_responses(vec![
+    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"status\": 200}".to_string(),
+  ]);
+  let (address, server) = start_test_server_with_responses(vec![format!(
+    "HTTP/1.1 202 Accepted\r\nLocation: {}/requests/1\r\n",
+    location_address
+  )]);
+  let publisher = WebhookPublisher {
+    http_client: HttpClient::new(RetryPolicy::default()).unwrap(),
+    config: WebhookConfig {
+      url: format!("{}/ingest", address),
+      headers: vec![("X-Api-Key".to_string(), "secret".to_string())]
+        .into_iter()
+        .collect(),
+      body_template: None,
+      content_type: None,
+      expected_statuses: None,
+    },
+    poll_config: PmConfig::from(RdfWorkerParameters::default()),
+  };
+
+  let graph = test_graph();
+  let event = test_event(&graph);
+  publisher
+    .publish(JobResult::new(0), &None, &event, &StopSignal::default())
+    .unwrap();
+
+  assert!(server.join().unwrap()[0].contains("x-api-key: secret\r\n"));
+  let request = &location_server.join().unwrap()[0];
+  assert!(request.starts_with("GET /requests/1 HTTP/1.1\r\n"));
+  assert!(!request.contains("x-api-key"));
+}